@@ -0,0 +1,225 @@
+# Messages for the booking site, in British English. Every message the site
+# uses is here; other locales fall back to these for anything they're missing.
+
+site-title = Stukeley Happenings
+loading = Loading...
+back = Back
+yes = yes
+
+close = Close
+
+## Booking form
+
+tickets-not-on-sale = Sorry, tickets aren't on sale at the moment.
+booking-contact = Booking Contact
+name-placeholder = Name
+name-required = Please enter your name
+email-placeholder = Email Address
+email-required = Please enter your email address
+email-invalid = Invalid email address: { $reason }
+phone-country = Phone number country
+phone-placeholder = Phone number
+phone-invalid = Please enter a valid phone number
+marketing-consent = Let me know about future village events
+ticket-label = Ticket { $number }
+ticket-type-option = { $name } - { $price }
+attendee-placeholder = Attendee name (optional)
+vegetarian = Vegetarian
+gluten-free = Gluten Free
+dietary-placeholder = Other dietary requirements
+add-on-quantity = How many { $add_on }
+add-on-each = { $price } each
+add-on-each-limited = { $price } each (up to { $max } per booking)
+donation = Donation
+donation-to = Donation to { $fund }
+donation-help = Add a donation to { $fund }, if you'd like to
+discount = Discount
+discount-code-placeholder = Discount code
+discount-apply = Apply
+discount-applied = { $code }: { $discount }
+discount-percentage = { $percent }% off
+discount-fixed = { $amount } off
+remove-ticket = Remove ticket { $number }
+add-ticket = Add Another Ticket
+add-bundle = Add { $name } - { $price }
+booking-in-progress = Booking...
+proceed-to-payment = Proceed to Payment
+book-now = Book Now
+start-again = Start Again
+creating-order = Creating Order...
+create-order-without-paying = Create Order without Paying
+booking-summary = Booking Summary
+confirm-and-pay = Confirm and Pay
+confirm-booking = Confirm Booking
+booking-failed-title = Oh dear
+booking-failed = We couldn't book your tickets.
+booking-failed-reference = Terribly sorry about that. If it keeps happening, please get in touch and quote reference
+create-order-results = Create Order Results
+create-order-greeting = Hi Sally
+order-created = Order Created: id: { $id }
+order-failed = Error Creating Order: { $error }
+order-pending = Pending..
+
+## Returning bookers
+
+booked-before = I've booked before
+email-me-a-code = Email me a code
+code-placeholder = Code from your email
+use-my-details = Use my details
+code-sent = If you've booked with us before we've emailed you a code.
+
+## Order summary
+
+order-tickets =
+    { $count ->
+        [one] Ticket { $first }
+       *[other] Tickets { $first }-{ $last }
+    }
+subtotal = Subtotal
+total = Total
+tickets = Tickets
+
+## After booking
+
+booked-in = Thanks, you're booked in!
+pay-on-the-door = There's nothing to pay now; we'll collect payment on the door.
+booking-reference = Your booking reference is
+
+## Phone number countries
+
+country-GB = United Kingdom
+country-IE = Ireland
+country-FR = France
+country-DE = Germany
+country-NL = Netherlands
+country-BE = Belgium
+country-ES = Spain
+country-PT = Portugal
+country-IT = Italy
+country-PL = Poland
+country-US = United States
+country-CA = Canada
+country-AU = Australia
+country-NZ = New Zealand
+country-IN = India
+country-ZA = South Africa
+
+## Problems with a booking
+
+check-field = Please check the { $field }
+attendee-name-too-long = Please keep names under 40 characters
+attendee-name-invalid = Please enter the attendee's name, or leave it blank
+dietary-too-long = Please keep dietary requirements under 100 characters
+enter-valid-email = Please enter a valid email address
+square-email-rejected = Square didn't accept that email address
+square-phone-rejected = Square didn't accept that phone number
+check-this = Please check this
+add-a-ticket = Please add at least one ticket
+not-taking-bookings = We aren't taking bookings for { $event }
+event-not-on-sale = Sorry, tickets for { $event } aren't on sale at the moment
+ticket-not-on-sale = { $ticket } tickets aren't on sale
+ticket-price-changed = { $ticket } tickets are now { $price }, please check your booking
+bundle-incomplete = Your { $ticket } doesn't match what it includes, please remove it and add it again
+bundle-too-big = { $ticket } has more tickets than we can take online, please get in touch to book it
+ticket-details-too-long = Please shorten this ticket's name or dietary requirements
+add-on-unavailable = { $add_on } isn't available
+add-on-changed = { $add_on } is now { $price }, please check your booking
+add-on-limit = Sorry, you can only have { $max } { $add_on } per booking
+pay-in-currency = Please pay in { $currency }
+donation-negative = A donation can't be negative
+donations-not-taken = This event isn't taking donations
+discount-unknown = We don't recognise that code
+discount-wrong-event = That code isn't valid for this event
+discount-expired = That code has expired
+discount-used-up = That code has already been used up
+discount-code-required = Please enter a code
+discount-unknown-event = There's no event with that id
+discount-percentage-range = A percentage discount has to be between 0 and 100
+discount-amount-positive = A fixed discount has to be more than nothing
+discount-currency = A fixed discount has to be in the event's currency
+booking-code-wrong = That code isn't right, or has expired
+phone-number-invalid = That phone number isn't valid
+refund-by-hand = Nothing was paid online for this booking, so it has to be refunded by hand
+
+## Problems on our side
+
+error-invalid-fields = Please check the details highlighted on the form.
+error-payment-provider = We couldn't set up payment with Square just now. Please try again in a few minutes.
+error-email = We couldn't send that email. Please try again later.
+error-config = The booking system isn't set up properly at the moment.
+error-unexpected = Something went wrong on our side. Please try again.
+error-with-reference = { $message } (reference { $reference })
+error-forbidden = Please sign in to the admin pages first.
+
+## Admin pages
+
+admin-password = Admin password
+sign-in = Sign in
+admin-password-wrong = That isn't the admin password
+
+bookings = Bookings
+add-ons = Add-ons
+bookings-failed = Error loading bookings: { $error }
+contact-name = Contact Name
+contact-email = Contact Email
+price = Price
+payment-type = Payment Type
+paid-amount = Paid Amount
+payment-none = None
+payment-card = Card
+payment-cash = Cash
+totals = Totals
+ticket-type = Ticket Type
+attendee = Attendee
+other-requirements = Other Requirements
+add-on = Add-on
+quantity = Quantity
+contacts = Contacts
+contacts-failed = Error loading contacts: { $error }
+name = Name
+email = Email
+phone = Phone
+total-spend = Total Spend
+dietary-preferences = Dietary Preferences
+marketing = Marketing
+possible-duplicates = Possible Duplicates
+merge-contact = Merge { $name } ({ $email })
+contact-unknown = We couldn't find one of those contacts, please reload the page
+contact-booking =
+    { $tickets ->
+        [one] { $event }: 1 ticket, paid { $paid }
+       *[other] { $event }: { $tickets } tickets, paid { $paid }
+    }
+discount-codes = Discount Codes
+discount-codes-failed = Error loading discount codes: { $error }
+add-a-code = Add a Code
+code = Code
+event = Event
+uses = Uses
+expires = Expires
+code-example = e.g. EARLYBIRD
+event-id = Event ID
+percentage = Percentage
+fixed-amount = Fixed Amount
+amount = Amount
+limits = Limits
+max-uses-placeholder = Maximum uses (optional)
+delete-code = Delete { $code }
+add-code = Add Code
+sending-email = Sending an email to sally...
+
+## Booking emails
+
+booking-code-subject = Your Stukeley Happenings booking code
+booking-code-body =
+    Hi { $name },
+
+    Your code is { $code }. It's valid for the next { $minutes } minutes.
+email-subject =
+    { $tickets ->
+        [one] Xmas Dinner: 1 ticket booked by { $name }
+       *[other] Xmas Dinner: { $tickets } tickets booked by { $name }
+    }
+email-booking-by = Booking By
+email-notes = Notes
+email-text-body = Switch to HTML View
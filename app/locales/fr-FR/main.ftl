@@ -0,0 +1,225 @@
+# Messages for the booking site, in French. Anything missing here is shown in
+# British English instead.
+
+site-title = Stukeley Happenings
+loading = Chargement...
+back = Retour
+yes = oui
+
+close = Fermer
+
+## Booking form
+
+tickets-not-on-sale = Désolé, les billets ne sont pas en vente pour le moment.
+booking-contact = Contact pour la réservation
+name-placeholder = Nom
+name-required = Veuillez saisir votre nom
+email-placeholder = Adresse e-mail
+email-required = Veuillez saisir votre adresse e-mail
+email-invalid = Adresse e-mail invalide : { $reason }
+phone-country = Pays du numéro de téléphone
+phone-placeholder = Numéro de téléphone
+phone-invalid = Veuillez saisir un numéro de téléphone valide
+marketing-consent = Tenez-moi informé(e) des prochains événements du village
+ticket-label = Billet { $number }
+ticket-type-option = { $name } - { $price }
+attendee-placeholder = Nom du participant (facultatif)
+vegetarian = Végétarien
+gluten-free = Sans gluten
+dietary-placeholder = Autres besoins alimentaires
+add-on-quantity = Quantité de { $add_on }
+add-on-each = { $price } l'unité
+add-on-each-limited = { $price } l'unité (jusqu'à { $max } par réservation)
+donation = Don
+donation-to = Don à { $fund }
+donation-help = Ajoutez un don à { $fund }, si vous le souhaitez
+discount = Réduction
+discount-code-placeholder = Code de réduction
+discount-apply = Appliquer
+discount-applied = { $code } : { $discount }
+discount-percentage = { $percent } % de réduction
+discount-fixed = { $amount } de réduction
+remove-ticket = Retirer le billet { $number }
+add-ticket = Ajouter un autre billet
+add-bundle = Ajouter { $name } - { $price }
+booking-in-progress = Réservation...
+proceed-to-payment = Passer au paiement
+book-now = Réserver
+start-again = Recommencer
+creating-order = Création de la commande...
+create-order-without-paying = Créer la commande sans payer
+booking-summary = Récapitulatif de la réservation
+confirm-and-pay = Confirmer et payer
+confirm-booking = Confirmer la réservation
+booking-failed-title = Oh là là
+booking-failed = Nous n'avons pas pu réserver vos billets.
+booking-failed-reference = Toutes nos excuses. Si cela se reproduit, contactez-nous en indiquant la référence
+create-order-results = Résultat de la commande
+create-order-greeting = Bonjour Sally
+order-created = Commande créée : { $id }
+order-failed = Erreur lors de la création de la commande : { $error }
+order-pending = En attente..
+
+## Returning bookers
+
+booked-before = J'ai déjà réservé
+email-me-a-code = M'envoyer un code par e-mail
+code-placeholder = Code reçu par e-mail
+use-my-details = Utiliser mes coordonnées
+code-sent = Si vous avez déjà réservé chez nous, nous vous avons envoyé un code par e-mail.
+
+## Order summary
+
+order-tickets =
+    { $count ->
+        [one] Billet { $first }
+       *[other] Billets { $first } à { $last }
+    }
+subtotal = Sous-total
+total = Total
+tickets = Billets
+
+## After booking
+
+booked-in = Merci, votre réservation est confirmée !
+pay-on-the-door = Rien à payer maintenant ; le paiement se fera à l'entrée.
+booking-reference = Votre référence de réservation est
+
+## Phone number countries
+
+country-GB = Royaume-Uni
+country-IE = Irlande
+country-FR = France
+country-DE = Allemagne
+country-NL = Pays-Bas
+country-BE = Belgique
+country-ES = Espagne
+country-PT = Portugal
+country-IT = Italie
+country-PL = Pologne
+country-US = États-Unis
+country-CA = Canada
+country-AU = Australie
+country-NZ = Nouvelle-Zélande
+country-IN = Inde
+country-ZA = Afrique du Sud
+
+## Problems with a booking
+
+check-field = Veuillez vérifier ce champ : { $field }
+attendee-name-too-long = Les noms doivent faire moins de 40 caractères
+attendee-name-invalid = Veuillez saisir le nom du participant, ou laisser vide
+dietary-too-long = Les besoins alimentaires doivent faire moins de 100 caractères
+enter-valid-email = Veuillez saisir une adresse e-mail valide
+square-email-rejected = Square n'a pas accepté cette adresse e-mail
+square-phone-rejected = Square n'a pas accepté ce numéro de téléphone
+check-this = Veuillez vérifier ceci
+add-a-ticket = Veuillez ajouter au moins un billet
+not-taking-bookings = Nous ne prenons pas de réservations pour { $event }
+event-not-on-sale = Désolé, les billets pour { $event } ne sont pas en vente pour le moment
+ticket-not-on-sale = Les billets { $ticket } ne sont pas en vente
+ticket-price-changed = Les billets { $ticket } coûtent désormais { $price }, veuillez vérifier votre réservation
+bundle-incomplete = Votre { $ticket } ne correspond pas à ce qu'elle comprend, veuillez la retirer et l'ajouter à nouveau
+bundle-too-big = { $ticket } comprend plus de billets que nous ne pouvons en prendre en ligne, veuillez nous contacter pour la réserver
+ticket-details-too-long = Veuillez raccourcir le nom ou les besoins alimentaires de ce billet
+add-on-unavailable = { $add_on } n'est pas disponible
+add-on-changed = { $add_on } coûte désormais { $price }, veuillez vérifier votre réservation
+add-on-limit = Désolé, vous ne pouvez prendre que { $max } { $add_on } par réservation
+pay-in-currency = Veuillez payer en { $currency }
+donation-negative = Un don ne peut pas être négatif
+donations-not-taken = Cet événement n'accepte pas de dons
+discount-unknown = Nous ne reconnaissons pas ce code
+discount-wrong-event = Ce code n'est pas valable pour cet événement
+discount-expired = Ce code a expiré
+discount-used-up = Ce code a déjà été entièrement utilisé
+discount-code-required = Veuillez saisir un code
+discount-unknown-event = Aucun événement ne porte cet identifiant
+discount-percentage-range = Une remise en pourcentage doit être comprise entre 0 et 100
+discount-amount-positive = Une remise fixe doit être supérieure à zéro
+discount-currency = Une remise fixe doit être dans la devise de l'événement
+booking-code-wrong = Ce code est incorrect, ou a expiré
+phone-number-invalid = Ce numéro de téléphone n'est pas valide
+refund-by-hand = Rien n'a été payé en ligne pour cette réservation, elle doit donc être remboursée manuellement
+
+## Problems on our side
+
+error-invalid-fields = Veuillez vérifier les informations signalées dans le formulaire.
+error-payment-provider = Nous n'avons pas pu préparer le paiement avec Square. Veuillez réessayer dans quelques minutes.
+error-email = Nous n'avons pas pu envoyer cet e-mail. Veuillez réessayer plus tard.
+error-config = Le système de réservation n'est pas correctement configuré pour le moment.
+error-unexpected = Un problème est survenu de notre côté. Veuillez réessayer.
+error-with-reference = { $message } (référence { $reference })
+error-forbidden = Veuillez d'abord vous connecter aux pages d'administration.
+
+## Admin pages
+
+admin-password = Mot de passe administrateur
+sign-in = Se connecter
+admin-password-wrong = Ce n'est pas le mot de passe administrateur
+
+bookings = Réservations
+add-ons = Options
+bookings-failed = Erreur lors du chargement des réservations : { $error }
+contact-name = Nom du contact
+contact-email = E-mail du contact
+price = Prix
+payment-type = Type de paiement
+paid-amount = Montant payé
+payment-none = Aucun
+payment-card = Carte
+payment-cash = Espèces
+totals = Totaux
+ticket-type = Type de billet
+attendee = Participant
+other-requirements = Autres besoins
+add-on = Option
+quantity = Quantité
+contacts = Contacts
+contacts-failed = Erreur lors du chargement des contacts : { $error }
+name = Nom
+email = E-mail
+phone = Téléphone
+total-spend = Total dépensé
+dietary-preferences = Préférences alimentaires
+marketing = Marketing
+possible-duplicates = Doublons possibles
+merge-contact = Fusionner { $name } ({ $email })
+contact-unknown = Nous n'avons pas trouvé l'un de ces contacts, veuillez recharger la page
+contact-booking =
+    { $tickets ->
+        [one] { $event } : { $tickets } billet, payé { $paid }
+       *[other] { $event } : { $tickets } billets, payé { $paid }
+    }
+discount-codes = Codes de réduction
+discount-codes-failed = Erreur lors du chargement des codes de réduction : { $error }
+add-a-code = Ajouter un code
+code = Code
+event = Événement
+uses = Utilisations
+expires = Expire le
+code-example = ex. EARLYBIRD
+event-id = Identifiant de l'événement
+percentage = Pourcentage
+fixed-amount = Montant fixe
+amount = Montant
+limits = Limites
+max-uses-placeholder = Nombre maximal d'utilisations (facultatif)
+delete-code = Supprimer { $code }
+add-code = Ajouter le code
+sending-email = Envoi d'un e-mail à Sally...
+
+## Booking emails
+
+booking-code-subject = Votre code de réservation Stukeley Happenings
+booking-code-body =
+    Bonjour { $name },
+
+    Votre code est { $code }. Il est valable pendant { $minutes } minutes.
+email-subject =
+    { $tickets ->
+        [one] Dîner de Noël : { $tickets } billet réservé par { $name }
+       *[other] Dîner de Noël : { $tickets } billets réservés par { $name }
+    }
+email-booking-by = Réservé par
+email-notes = Remarques
+email-text-body = Passez à l'affichage HTML
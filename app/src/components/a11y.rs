@@ -0,0 +1,41 @@
+//! Ids that tie labels, help and messages to the controls they're about, so
+//! screen readers announce them together.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use leptos::*;
+
+#[derive(Clone, Copy)]
+struct Ids(StoredValue<usize>);
+
+/// Starts handing out element ids for the page. The app does this once, at the top.
+pub fn provide_ids() { provide_context(Ids(store_value(0))); }
+
+/// An id for an element that others refer to, e.g. `email-3`. They're handed out
+/// in the order components render, which is the same on the server as when the
+/// browser hydrates the page, so both agree on them.
+pub fn unique_id(prefix: &str) -> String {
+    static OUTSIDE_APP: AtomicUsize = AtomicUsize::new(0);
+    let n = match use_context::<Ids>() {
+        Some(Ids(next)) => {
+            let n = next.get_value();
+            next.set_value(n + 1);
+            n
+        }
+        // Rendered on its own, e.g. for an email, where there's nothing to agree with.
+        None => OUTSIDE_APP.fetch_add(1, Ordering::Relaxed),
+    };
+    format!("{}-{}", prefix, n)
+}
+
+/// The ids of whichever of `descriptions` are showing, for `aria-describedby`.
+pub fn described_by(descriptions: Vec<(String, Signal<bool>)>) -> Signal<Option<String>> {
+    Signal::derive(move || {
+        let showing = descriptions
+            .iter()
+            .filter(|(_, showing)| showing.get())
+            .map(|(id, _)| id.as_str())
+            .collect::<Vec<_>>();
+        (!showing.is_empty()).then(|| showing.join(" "))
+    })
+}
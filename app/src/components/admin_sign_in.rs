@@ -0,0 +1,54 @@
+use crate::components::*;
+use crate::error::describe;
+use crate::i18n::use_i18n;
+use crate::server_fns::sign_in;
+use leptos::*;
+
+/// Asks for the admin password, for a page only admins can use, and calls
+/// `signed_in` once it's right so the page can try again.
+#[component]
+pub fn AdminSignIn<F>(signed_in: F) -> impl IntoView
+where
+    F: Fn() + 'static,
+{
+    let i18n = use_i18n();
+    let id = unique_id("admin-password");
+    let (password, set_password) = create_signal(String::new());
+    let attempt = create_action(|password: &String| {
+        let password = password.clone();
+        async move { sign_in(password).await }
+    });
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = attempt.value().get() {
+            signed_in();
+        }
+    });
+    let error = Signal::derive(move || match attempt.value().get() {
+        Some(Err(e)) => Some(describe(&e)),
+        _ => None,
+    });
+
+    view! {
+      <div class="box">
+        <Field label=move || i18n.t("admin-password") error=error>
+          <div class="control">
+            <label class="is-sr-only" for=id.clone()>
+              {i18n.t("admin-password")}
+            </label>
+            <input
+              id=id
+              class="input"
+              type="password"
+              autocomplete="current-password"
+              prop:value=password
+              on:input=move |ev| set_password(event_target_value(&ev))
+            />
+          </div>
+          <IconButton color=Color::Primary on_click=move || attempt.dispatch(password.get_untracked())>
+            {i18n.t("sign-in")}
+          </IconButton>
+        </Field>
+      </div>
+    }
+}
@@ -0,0 +1,44 @@
+use crate::components::unique_id;
+use crate::i18n::use_i18n;
+use crate::model::*;
+use leptos::*;
+
+#[component]
+pub fn AddOnQuantity(
+    add_on: AddOn,
+    #[prop(into)] get: Signal<u32>,
+    #[prop(into)] set: Callback<u32>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let (id, help_id) = (unique_id("add-on"), unique_id("add-on-help"));
+    let max = add_on.max_per_booking;
+    let price = i18n.money(&add_on.price);
+    let help = match max {
+        Some(m) => i18n.t_with("add-on-each-limited", &[("price", price.into()), ("max", m.into())]),
+        None => i18n.t_with("add-on-each", &[("price", price.into())]),
+    };
+
+    view! {
+      <div class="control">
+        <label class="is-sr-only" for=id.clone()>
+          {i18n.t_with("add-on-quantity", &[("add_on", add_on.name.clone().into())])}
+        </label>
+        <input
+          id=id
+          class="input"
+          type="number"
+          min="0"
+          max=max.map(|m| m.to_string())
+          aria-describedby=help_id.clone()
+          prop:value=move || get().to_string()
+          on:change=move |ev| {
+              let quantity = event_target_value(&ev).parse::<u32>().unwrap_or(0);
+              set(max.map_or(quantity, |m| quantity.min(m)))
+          }
+        />
+      </div>
+      <p class="help" id=help_id>
+        {help}
+      </p>
+    }
+}
@@ -1,3 +1,4 @@
+use crate::components::unique_id;
 use leptos::*;
 
 #[component]
@@ -6,13 +7,13 @@ pub fn Checkbox(
     #[prop(into)] get: Signal<bool>,
     #[prop(into)] set: Callback<bool>,
 ) -> impl IntoView {
+    let id = unique_id("checkbox");
     view! {
       <div class="control">
-        <label class="checkbox">
-          <input type="checkbox" prop:checked=get on:change=move |ev| set(event_target_checked(&ev))/>
+        <label class="checkbox" for=id.clone()>
+          <input id=id type="checkbox" prop:checked=get on:change=move |ev| set(event_target_checked(&ev))/>
           {format!(" {} ", label)}
         </label>
       </div>
     }
 }
-
@@ -0,0 +1,70 @@
+use crate::components::*;
+use crate::error::describe;
+use crate::i18n::use_i18n;
+use crate::model::*;
+use crate::server_fns::check_discount_code;
+use leptos::*;
+
+#[component]
+pub fn DiscountCodeEntry(
+    #[prop(into)] event_id: String,
+    #[prop(into)] get: Signal<Option<DiscountCode>>,
+    #[prop(into)] set: Callback<Option<DiscountCode>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let (id, message_id) = (unique_id("discount-code"), unique_id("discount-code-message"));
+    let (code, set_code) = create_signal(String::new());
+
+    let check = create_action(move |code: &String| {
+        let (event_id, code) = (event_id.clone(), code.clone());
+        async move { check_discount_code(event_id, code).await }
+    });
+
+    create_effect(move |_| match check.value().get() {
+        Some(Ok(discount)) => set(Some(discount)),
+        Some(Err(_)) => set(None),
+        None => {}
+    });
+
+    let message = move || match (get(), check.value().get()) {
+        (Some(discount), _) => {
+            let applied = i18n.t_with(
+                "discount-applied",
+                &[("code", discount.code.clone().into()), ("discount", i18n.discount(&discount).into())],
+            );
+            Some(view! { <p class="help is-success">{applied}</p> })
+        }
+        (None, Some(Err(e))) => Some(view! { <p class="help is-danger">{describe(&e)}</p> }),
+        _ => None,
+    };
+    let has_message = Signal::derive(move || get.with(Option::is_some) || matches!(check.value().get(), Some(Err(_))));
+    let describedby = described_by(vec![(message_id.clone(), has_message)]);
+
+    view! {
+      <div class="field has-addons">
+        <p class="control">
+          <label class="is-sr-only" for=id.clone()>
+            {i18n.t("discount-code-placeholder")}
+          </label>
+          <input
+            id=id
+            class="input"
+            type="text"
+            placeholder=i18n.t("discount-code-placeholder")
+            aria-describedby=move || describedby()
+            prop:value=code
+            on:change=move |ev| set_code(event_target_value(&ev))
+          />
+        </p>
+        <p class="control">
+          <IconButton disabled=check.pending() on_click=move || check.dispatch(code.get_untracked())>
+            {i18n.t("discount-apply")}
+          </IconButton>
+        </p>
+      </div>
+      // Announced as it changes, since it arrives after the code's been checked.
+      <div id=message_id aria-live="polite">
+        {message}
+      </div>
+    }
+}
@@ -0,0 +1,47 @@
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+use crate::components::unique_id;
+use crate::i18n::use_i18n;
+use crate::money::Money;
+use leptos::*;
+
+#[component]
+pub fn DonationAmount(
+    #[prop(into)] fund: String,
+    #[prop(into)] get: Signal<Money>,
+    #[prop(into)] set: Callback<Money>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let (id, help_id) = (unique_id("donation"), unique_id("donation-help"));
+    let places = move || get().currency.minor_units();
+
+    view! {
+      <div class="control has-icons-left">
+        <label class="is-sr-only" for=id.clone()>
+          {i18n.t("donation")}
+        </label>
+        <input
+          id=id
+          class="input"
+          type="number"
+          min="0"
+          aria-describedby=help_id.clone()
+          step=move || Decimal::new(1, places()).to_string()
+          placeholder=move || i18n.number(Decimal::ZERO, places())
+          prop:value=move || { if get().is_zero() { "".to_string() } else { get().amount.to_string() } }
+          on:change=move |ev| {
+              let currency = get.get_untracked().currency;
+              let amount = Decimal::from_str(&event_target_value(&ev)).unwrap_or_default();
+              set(Money::new(amount.max(Decimal::ZERO), currency).round())
+          }
+        />
+        <span class="icon is-small is-left" aria-hidden="true">
+          {move || get().currency.symbol()}
+        </span>
+      </div>
+      <p class="help" id=help_id>
+        {i18n.t_with("donation-help", &[("fund", fund.into())])}
+      </p>
+    }
+}
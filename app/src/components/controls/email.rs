@@ -1,3 +1,5 @@
+use crate::components::{described_by, unique_id};
+use crate::i18n::use_i18n;
 use email_address::*;
 use leptos::logging::*;
 use leptos::*;
@@ -9,29 +11,37 @@ pub fn Email(
     #[prop(into)] get: Signal<String>,
     #[prop(into)] set: Callback<String>,
 ) -> impl IntoView {
+    let i18n = use_i18n();
+    let (id, error_id) = (unique_id("email"), unique_id("email-error"));
     let email_address = Signal::derive(move || EmailAddress::from_str(&get()));
+    let is_invalid = Signal::derive(move || email_address().is_err());
+    let describedby = described_by(vec![(error_id.clone(), is_invalid)]);
     let email_err = move || match email_address() {
         Ok(_) => None,
         Err(e) => {
             let msg = if get().is_empty() {
-                "Please enter your email address".to_string()
+                i18n.t("email-required")
             } else {
-                format!("Invalid email address: {}", e)
+                i18n.t_with("email-invalid", &[("reason", e.to_string().into())])
             };
-            Some(view! { <p class="help is-danger">{msg}</p> })
+            Some(view! {
+              <p class="help is-danger" id=error_id.clone()>
+                {msg}
+              </p>
+            })
         }
     };
 
     let email_right_icon = move || {
         if email_address().is_ok() {
             Some(view! {
-              <span class="icon is-small is-right">
+              <span class="icon is-small is-right" aria-hidden="true">
                 <Icon icon=Icon::from(FaCheckSolid)/>
               </span>
             })
         } else {
             Some(view! {
-              <span class="icon is-small is-right">
+              <span class="icon is-small is-right" aria-hidden="true">
                 <Icon icon=Icon::from(FaTriangleExclamationSolid)/>
               </span>
             })
@@ -40,12 +50,20 @@ pub fn Email(
 
     view! {
       <div class="control has-icons-left has-icons-right">
+        <label class="is-sr-only" for=id.clone()>
+          {i18n.t("email-placeholder")}
+        </label>
         <input
+          id=id
           class="input"
           class:is-success=move || { email_address().is_ok() }
           class:is-danger=move || { email_address().is_err() }
-          type="text"
-          placeholder="Email Address"
+          type="email"
+          autocomplete="email"
+          placeholder=i18n.t("email-placeholder")
+          aria-required="true"
+          aria-invalid=move || is_invalid().to_string()
+          aria-describedby=move || describedby()
           prop:value=get
           on:input=move |ev| {
               log!("yay: {:?}", email_address());
@@ -53,7 +71,7 @@ pub fn Email(
           }
         />
 
-        <span class="icon is-small is-left">
+        <span class="icon is-small is-left" aria-hidden="true">
           <Icon icon=Icon::from(FaEnvelopeSolid)/>
         </span>
         {email_right_icon}
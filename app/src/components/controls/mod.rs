@@ -1,15 +1,23 @@
+mod add_on;
 mod checkbox;
+mod discount_code;
+mod donation;
 mod email;
 mod name;
 mod phonenumber;
+mod returning_booker;
 mod text;
 mod ticket;
 mod ticket_type;
 
+pub use add_on::*;
 pub use checkbox::*;
+pub use discount_code::*;
+pub use donation::*;
 pub use email::*;
 pub use name::*;
 pub use phonenumber::*;
+pub use returning_booker::*;
 pub use text::*;
 pub use ticket::*;
 pub use ticket_type::*;
@@ -1,3 +1,5 @@
+use crate::components::{described_by, unique_id};
+use crate::i18n::use_i18n;
 use leptos::*;
 use leptos_icons::{FaIcon::*, Icon};
 
@@ -6,33 +8,39 @@ pub fn Name(
     #[prop(into)] get: MaybeSignal<String>,
     #[prop(into)] set: Callback<String>,
 ) -> impl IntoView {
-    let name_err = {
+    let i18n = use_i18n();
+    let (id, error_id) = (unique_id("name"), unique_id("name-error"));
+    let is_missing = {
         let get = get.clone();
-        move || {
-            if get().is_empty() {
-                Some(view! { <p class="help is-danger">"Please enter your name"</p> })
-            } else {
-                None
-            }
-        }
+        Signal::derive(move || get.with(String::is_empty))
+    };
+    let describedby = described_by(vec![(error_id.clone(), is_missing)]);
+    let name_err = move || {
+        is_missing().then(|| view! { <p class="help is-danger" id=error_id.clone()>{i18n.t("name-required")}</p> })
     };
-    let name_err = Signal::derive(name_err);
     view! {
       <div class="control has-icons-left">
+        <label class="is-sr-only" for=id.clone()>
+          {i18n.t("name-placeholder")}
+        </label>
         <input
+          id=id
           class="input"
-          class:is-success=move || { name_err().is_none() }
-          class:is-danger=move || { name_err().is_some() }
+          class:is-success=move || !is_missing()
+          class:is-danger=move || is_missing()
           type="text"
-          placeholder="Name"
+          autocomplete="name"
+          placeholder=i18n.t("name-placeholder")
+          aria-required="true"
+          aria-invalid=move || is_missing().to_string()
+          aria-describedby=move || describedby()
           prop:value=get
           on:change=move |ev| set(event_target_value(&ev))
         />
-        <span class="icon is-small is-left">
+        <span class="icon is-small is-left" aria-hidden="true">
           <Icon icon=Icon::from(FaUserSolid)/>
         </span>
       </div>
       {name_err}
     }
 }
-
@@ -1,31 +1,85 @@
-extern crate phonenumber as pn;
-
+use crate::components::{described_by, unique_id};
+use crate::i18n::use_i18n;
+use crate::model::{phone_region, to_e164, validate_phone_no, PHONE_REGIONS};
 use leptos::*;
 
 #[component]
 pub fn PhoneNumber(
     #[prop(into)] get: Signal<String>,
     #[prop(into)] set: Callback<String>,
+    #[prop(into)] region: Signal<String>,
+    #[prop(into)] set_region: Callback<String>,
 ) -> impl IntoView {
-    let on_change = move |ev: leptos::ev::Event| set(event_target_value(&ev));
+    let i18n = use_i18n();
+    let (country_id, id, error_id) = (unique_id("phone-country"), unique_id("phone"), unique_id("phone-error"));
 
-    let is_valid = move || {
-        get.with(|s| match pn::parse(Some(pn::country::Id::GB), s) {
-            Ok(pn) => pn.is_valid(),
-            Err(_) => false,
-        })
+    // Kept in E.164 form once it makes sense for the region, and as typed until
+    // then so the booker can see what to fix.
+    let normalise = move |phone: String, region: &str| to_e164(&phone, region).unwrap_or(phone);
+
+    let on_change = move |ev: leptos::ev::Event| {
+        set(normalise(event_target_value(&ev), &region.get_untracked()))
+    };
+    let on_region_change = move |ev: leptos::ev::Event| {
+        let new_region = event_target_value(&ev);
+        set(normalise(get.get_untracked(), &new_region));
+        set_region(new_region);
     };
 
+    let options = PHONE_REGIONS
+        .iter()
+        .map(|code| {
+            let id = phone_region(code);
+            let is_selected = move || region.with(|r| phone_region(r) == id);
+            view! {
+              <option selected=is_selected value=*code>
+                {i18n.t(&format!("country-{}", code))}
+              </option>
+            }
+        })
+        .collect_view();
+
+    let is_invalid = Signal::derive(move || get.with(|s| validate_phone_no(s).is_err()));
+    let describedby = described_by(vec![(error_id.clone(), is_invalid)]);
+
     let error_msg = move || {
-        (!is_valid())
-            .then_some(view! { <p class="help is-danger">Please enter a valid phone number</p> })
+        is_invalid().then(|| {
+            view! {
+              <p class="help is-danger" id=error_id.clone()>
+                {i18n.t("phone-invalid")}
+              </p>
+            }
+        })
     };
 
     view! {
+      <div class="control">
+        <label class="is-sr-only" for=country_id.clone()>
+          {i18n.t("phone-country")}
+        </label>
+        <div class="select">
+          <select id=country_id on:change=on_region_change>
+            {options}
+          </select>
+        </div>
+      </div>
       <p class="control is-expanded">
-        <input class="input" type="tel" placeholder="Phone number (optional)" prop:value=get on:change=on_change/>
+        <label class="is-sr-only" for=id.clone()>
+          {i18n.t("phone-placeholder")}
+        </label>
+        <input
+          id=id
+          class="input"
+          type="tel"
+          autocomplete="tel"
+          placeholder=i18n.t("phone-placeholder")
+          aria-required="true"
+          aria-invalid=move || is_invalid().to_string()
+          aria-describedby=move || describedby()
+          prop:value=get
+          on:change=on_change
+        />
       </p>
       <div>{error_msg}</div>
     }
 }
-
@@ -0,0 +1,86 @@
+use crate::components::controls::*;
+use crate::components::*;
+use crate::error::describe;
+use crate::i18n::use_i18n;
+use crate::model::*;
+use crate::server_fns::{send_booking_code, verify_booking_code};
+use leptos::*;
+use leptos_icons::FaIcon::*;
+
+/// Lets someone who has booked before fill in their details from last time,
+/// after proving they own the email address with a one-time code.
+#[component]
+pub fn ReturningBooker(#[prop(into)] on_found: Callback<PreviousBooking>) -> impl IntoView {
+    let i18n = use_i18n();
+    let (open, set_open) = create_signal(false);
+    let (email, set_email) = create_signal(String::new());
+    let (code, set_code) = create_signal(String::new());
+
+    let send_code = create_action(move |_: &()| {
+        let email = email.get_untracked();
+        async move { send_booking_code(email).await }
+    });
+    let verify = create_action(move |_: &()| {
+        let (email, code) = (email.get_untracked(), code.get_untracked());
+        async move { verify_booking_code(email, code).await }
+    });
+
+    let code_sent = move || matches!(send_code.value().get(), Some(Ok(_)));
+    let message = move || match (send_code.value().get(), verify.value().get()) {
+        (_, Some(Err(e))) | (Some(Err(e)), _) => Some(view! { <p class="help is-danger">{describe(&e)}</p> }),
+        (Some(Ok(_)), None) => {
+            Some(view! { <p class="help">{i18n.t("code-sent")}</p> })
+        }
+        _ => None,
+    };
+
+    create_effect(move |_| {
+        if let Some(Ok(previous)) = verify.value().get() {
+            on_found(previous);
+            set_open(false);
+        }
+    });
+
+    view! {
+      <Show
+        when=open
+        fallback=move || {
+            view! {
+              <p class="control">
+                <IconButton icon=FaUserSolid size=Size::Small on_click=move || set_open(true)>
+                  {i18n.t("booked-before")}
+                </IconButton>
+              </p>
+            }
+        }
+      >
+
+        <Email get=email set=move |new| set_email(new)/>
+        <Show
+          when=code_sent
+          fallback=move || {
+              view! {
+                <p class="control">
+                  <IconButton
+                    icon=FaEnvelopeSolid
+                    disabled=send_code.pending()
+                    on_click=move || send_code.dispatch(())
+                  >
+                    {i18n.t("email-me-a-code")}
+                  </IconButton>
+                </p>
+              }
+          }
+        >
+
+          <Text placeholder=i18n.t("code-placeholder") get=code set=move |new| set_code(new)/>
+          <p class="control">
+            <IconButton icon=FaCheckSolid disabled=verify.pending() on_click=move || verify.dispatch(())>
+              {i18n.t("use-my-details")}
+            </IconButton>
+          </p>
+        </Show>
+        {message}
+      </Show>
+    }
+}
@@ -1,3 +1,4 @@
+use crate::components::{described_by, unique_id};
 use leptos::*;
 use leptos_icons::{FaIcon, Icon};
 
@@ -8,31 +9,56 @@ pub fn Text(
     #[prop(into)] get: MaybeSignal<String>,
     #[prop(into)] set: Callback<String>,
     #[prop(into, optional)] icon: Option<FaIcon>,
+    /// What's wrong with what's been entered, if anything.
+    #[prop(into, optional)]
+    error: Option<Signal<Option<String>>>,
 ) -> impl IntoView {
+    let (id, error_id) = (unique_id("text"), unique_id("text-error"));
+    let has_error = Signal::derive(move || error.is_some_and(|e| e.with(Option::is_some)));
+    let describedby = described_by(vec![(error_id.clone(), has_error)]);
+
     let icon_view = icon.map(|i| {
         view! {
-          <span class=format!("icon is-small is-left")>
+          <span class=format!("icon is-small is-left") aria-hidden="true">
             <Icon icon=Icon::from(i)/>
           </span>
         }
     });
 
-    let label_view = label.map(|l| {
-        view! { <label class="label">{l}</label> }
-    });
+    // Without a visible label, the placeholder names the input for screen readers,
+    // as it disappears once something's typed.
+    let label_view = match (label, placeholder.clone()) {
+        (Some(l), _) => Some(view! { <label class="label" for=id.clone()>{l}</label> }),
+        (None, Some(p)) => Some(view! { <label class="is-sr-only" for=id.clone()>{p}</label> }),
+        (None, None) => None,
+    };
+
+    let error_view = move || {
+        error.and_then(|e| e()).map(|msg| {
+            view! {
+              <p class="help is-danger" id=error_id.clone()>
+                {msg}
+              </p>
+            }
+        })
+    };
 
     view! {
       {label_view}
       <div class="control" class:has-icons-left=icon_view.is_some()>
         <input
+          id=id
           class="input"
+          class:is-danger=move || has_error()
           type="text"
           placeholder=placeholder
+          aria-invalid=move || has_error().to_string()
+          aria-describedby=move || describedby()
           prop:value=get
           on:change=move |ev| set(event_target_value(&ev))
         />
         {icon_view}
       </div>
+      {error_view}
     }
 }
-
@@ -1,9 +1,12 @@
 use crate::components::controls::*;
+use crate::i18n::use_i18n;
 use crate::model::*;
 use leptos::*;
+use validator::Validate;
 
 #[component]
 pub fn TicketControl(ticket: RwSignal<Ticket>) -> impl IntoView {
+    let i18n = use_i18n();
     let tt = Signal::derive(move || ticket().ticket_type);
     let set_tt = move |new| ticket.update(|g| g.ticket_type = new);
 
@@ -16,11 +19,33 @@ pub fn TicketControl(ticket: RwSignal<Ticket>) -> impl IntoView {
     let reqs = Signal::derive(move || ticket().dietary_requirements);
     let set_reqs = move |new| ticket.update(|g| g.dietary_requirements = new);
 
+    let name = Signal::derive(move || ticket().attendee_name);
+    let set_name = move |new| ticket.update(|g| g.attendee_name = new);
+
+    let errors = Signal::derive(move || {
+        ticket.with(|t| t.validate().err().map(|e| validation_messages(&e, &i18n)).unwrap_or_default())
+    });
+    let error_for = move |field: &'static str| Signal::derive(move || errors.with(|e| e.get(field).cloned()));
+
+    let ticket_type_view = match ticket.get_untracked().bundle {
+        Some(member) => {
+            let label = format!("{}: {}", ticket.get_untracked().ticket_type.name, member.label);
+            view! {
+              <div class="control">
+                <span class="tag is-medium">{label}</span>
+              </div>
+            }
+            .into_view()
+        }
+        None => view! { <TicketType get=tt set=set_tt/> }.into_view(),
+    };
+
     view! {
-      <TicketType get=tt set=set_tt/>
-      <Checkbox label="Vegetarian" get=veg set=set_veg/>
-      <Checkbox label="Gluten Free" get=gf set=set_gf/>
-      <Text placeholder="Other dietry requirements" get=reqs set=set_reqs/>
+      {ticket_type_view}
+      <Text placeholder=i18n.t("attendee-placeholder") get=name set=set_name error=error_for("attendee_name")/>
+      <Checkbox label=i18n.t("vegetarian") get=veg set=set_veg/>
+      <Checkbox label=i18n.t("gluten-free") get=gf set=set_gf/>
+      <Text placeholder=i18n.t("dietary-placeholder") get=reqs set=set_reqs error=error_for("dietary_requirements")/>
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::i18n::use_i18n;
 use crate::model::*;
 use leptos::logging::*;
 use leptos::*;
@@ -7,17 +8,21 @@ pub fn TicketType(
     #[prop(into)] get: Signal<TicketType>,
     #[prop(into)] set: Callback<TicketType>,
 ) -> impl IntoView {
+    let i18n = use_i18n();
     let ticket_types = use_context::<StoredValue<TicketTypes>>().expect("there to be ticket types");
 
     let options = ticket_types()
-        .clone()
+        .singles()
         .into_iter()
         .map(|tt| {
             let is_selected = {
                 let tt = tt.clone();
                 move || tt.name == get().name
             };
-            let option_text = format!("{} - £{}", tt.name, tt.price);
+            let option_text = i18n.t_with(
+                "ticket-type-option",
+                &[("name", tt.name.clone().into()), ("price", i18n.money(&tt.price).into())],
+            );
             view! {
               <option selected=is_selected value=tt.name>
                 {option_text}
@@ -29,7 +34,7 @@ pub fn TicketType(
     view! {
       <div class="control">
         <div class="select">
-          <select on:change=move |ev| {
+          <select aria-label=i18n.t("ticket-type") on:change=move |ev| {
               log!("{}", event_target_value(& ev));
               ticket_types().find(event_target_value(&ev)).map(set);
           }>{options}</select>
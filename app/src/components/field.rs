@@ -1,9 +1,23 @@
+use crate::components::{described_by, unique_id};
 use leptos::*;
 
+/// A labelled row of the form. The label names the controls in it as a group;
+/// each control still has its own name, for when it's reached on its own.
 #[component]
-pub fn Field(children: Children, #[prop(optional, into)] label: ViewFn) -> impl IntoView
+pub fn Field(
+    children: Children,
+    #[prop(optional, into)] label: Option<ViewFn>,
+    /// A problem the server found with what's in the field.
+    #[prop(optional, into)]
+    error: Option<Signal<Option<String>>>,
+) -> impl IntoView
 where
 {
+    let (label_id, error_id) = (unique_id("field-label"), unique_id("field-error"));
+    let is_labelled = label.is_some();
+    let has_error = Signal::derive(move || error.is_some_and(|e| e.with(Option::is_some)));
+    let describedby = described_by(vec![(error_id.clone(), has_error)]);
+
     let children = children()
         .nodes
         .into_iter()
@@ -11,14 +25,36 @@ where
             view! { <div class="field">{child}</div> }
         })
         .collect_view();
+    let error = move || {
+        error
+            .and_then(|e| e())
+            .map(|msg| {
+                view! {
+                  <div class="field">
+                    <p class="help is-danger" id=error_id.clone() role="alert">
+                      {msg}
+                    </p>
+                  </div>
+                }
+            })
+    };
 
     view! {
       <div class="field is-horizontal">
         <div class="field-label is-normal">
-          <label class="label">{label.run()}</label>
+          <label class="label" id=label_id.clone()>
+            {label.map(|l| l.run())}
+          </label>
+        </div>
+        <div
+          class="field-body"
+          role=is_labelled.then_some("group")
+          aria-labelledby=is_labelled.then_some(label_id)
+          aria-describedby=move || describedby()
+        >
+          {children}
+          {error}
         </div>
-        <div class="field-body">{children}</div>
       </div>
     }
 }
-
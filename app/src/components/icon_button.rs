@@ -69,6 +69,9 @@ pub fn IconButton<F>(
     #[prop(into, optional)] color: Color,
     #[prop(into, optional)] size: Size,
     #[prop(into, optional)] class: OptionalMaybeSignal<String>,
+    /// What the button does, for screen readers, when it's only an icon.
+    #[prop(into, optional)]
+    label: Option<String>,
     #[prop(optional)] children: Option<Children>,
     #[prop(into, optional)] disabled: OptionalMaybeSignal<bool>,
 ) -> impl IntoView
@@ -77,7 +80,7 @@ where
 {
     let icon_view = icon.map(|i| {
         view! {
-          <span class=format!("icon {}", size)>
+          <span class=format!("icon {}", size) aria-hidden="true">
             <Icon icon=Icon::from(i)/>
           </span>
         }
@@ -87,6 +90,7 @@ where
       <button
         disabled=move || disabled.or_default().get()
         class=format!("button {} {}", color, class.or_default().get())
+        aria-label=label
         on:click=move |_| on_click()
       >
         {icon_view}
@@ -1,9 +1,15 @@
+mod a11y;
+mod admin_sign_in;
 pub mod controls;
 mod field;
 mod icon_button;
 mod modal;
+mod order_summary;
 
+pub use a11y::*;
+pub use admin_sign_in::*;
 pub use field::*;
 pub use icon_button::*;
 pub use modal::*;
+pub use order_summary::*;
 
@@ -1,5 +1,15 @@
+use crate::components::unique_id;
+use crate::i18n::use_i18n;
+use leptos::html::Div;
 use leptos::*;
+use wasm_bindgen::JsCast;
 
+/// Everything in a dialog that can take focus with the keyboard.
+const FOCUSABLE: &str = "a[href], button:not([disabled]), input:not([disabled]), select:not([disabled]), \
+                         textarea:not([disabled]), [tabindex]:not([tabindex='-1'])";
+
+/// A dialog over the page. While it's open, focus moves into it and Tab keeps
+/// focus inside it; Escape closes it, and focus goes back to where it was.
 #[component]
 pub fn Modal<F, G>(
     active: F,
@@ -12,13 +22,76 @@ where
     F: Fn() -> bool + 'static,
     G: Fn() + 'static,
 {
+    let i18n = use_i18n();
+    let title_id = unique_id("modal-title");
+    let active = Signal::derive(active);
+    let close = store_value(close_requested);
+    let card = create_node_ref::<Div>();
+    let opened_from = store_value(None::<web_sys::HtmlElement>);
+
+    create_effect(move |_| {
+        if active() {
+            opened_from.set_value(document().active_element().and_then(|e| e.dyn_into().ok()));
+            // Wait for the dialog to be shown; hidden elements can't take focus.
+            request_animation_frame(move || {
+                if let Some(card) = card.get_untracked() {
+                    let first = focusable(&card).into_iter().next();
+                    let _ = first.map_or_else(|| card.focus(), |el| el.focus());
+                }
+            });
+        } else if let Some(el) = opened_from.get_value() {
+            opened_from.set_value(None);
+            let _ = el.focus();
+        }
+    });
+
+    let on_keydown = move |ev: ev::KeyboardEvent| match ev.key().as_str() {
+        "Escape" => {
+            ev.prevent_default();
+            close.with_value(|close| close());
+        }
+        "Tab" => {
+            let Some(card) = card.get_untracked() else {
+                return;
+            };
+            let elements = focusable(&card);
+            let (Some(first), Some(last)) = (elements.first(), elements.last()) else {
+                ev.prevent_default();
+                return;
+            };
+            let focused = document().active_element();
+            let is_focused = |el: &web_sys::HtmlElement| focused.as_ref() == Some::<&web_sys::Element>(el.as_ref());
+            if ev.shift_key() && is_focused(first) {
+                ev.prevent_default();
+                let _ = last.focus();
+            } else if !ev.shift_key() && is_focused(last) {
+                ev.prevent_default();
+                let _ = first.focus();
+            }
+        }
+        _ => {}
+    };
+
     view! {
-      <div class="modal" class:is-active=active>
+      <div class="modal" class:is-active=move || active() on:keydown=on_keydown>
         <div class="modal-background"></div>
-        <div class="modal-card">
+        <div
+          class="modal-card"
+          node_ref=card
+          role="dialog"
+          aria-modal="true"
+          aria-labelledby=title_id.clone()
+          tabindex="-1"
+        >
           <header class="modal-card-head">
-            <p class="modal-card-title">{title}</p>
-            <button class="delete" aria-label="close" on:click=move |_| close_requested()></button>
+            <h2 class="modal-card-title" id=title_id>
+              {title}
+            </h2>
+            <button
+              class="delete"
+              aria-label=i18n.t("close")
+              on:click=move |_| close.with_value(|close| close())
+            ></button>
           </header>
           <section class="modal-card-body">{children()}</section>
           <footer class="modal-card-foot">{footer.run()}</footer>
@@ -27,3 +100,12 @@ where
     }
 }
 
+fn focusable(card: &web_sys::HtmlElement) -> Vec<web_sys::HtmlElement> {
+    let Ok(nodes) = card.query_selector_all(FOCUSABLE) else {
+        return vec![];
+    };
+    (0..nodes.length())
+        .filter_map(|i| nodes.item(i))
+        .filter_map(|node| node.dyn_into().ok())
+        .collect()
+}
@@ -0,0 +1,93 @@
+use crate::i18n::use_i18n;
+use crate::model::*;
+use leptos::*;
+
+#[component]
+pub fn OrderSummary(#[prop(into)] booking: Signal<NewBooking>) -> impl IntoView {
+    let i18n = use_i18n();
+    let totals = Signal::derive(move || booking.with(|b| b.totals()));
+
+    let ticket_rows = move || {
+        booking.with(|b| {
+            let mut n = 0;
+            ticket_groups(&b.tickets)
+                .into_iter()
+                .map(|group| {
+                    let first = n + 1;
+                    n += group.len();
+                    let tickets = i18n.t_with(
+                        "order-tickets",
+                        &[("count", group.len().into()), ("first", first.into()), ("last", n.into())],
+                    );
+                    let tt = &group[0].ticket_type;
+                    view! {
+                      <tr>
+                        <td>{tickets}</td>
+                        <td>{tt.name.clone()}</td>
+                        <td class="has-text-right">{i18n.money(&tt.price)}</td>
+                      </tr>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    let add_on_rows = move || {
+        booking.with(|b| {
+            b.add_ons
+                .iter()
+                .map(|o| {
+                    view! {
+                      <tr>
+                        <td>{o.add_on.name.clone()}</td>
+                        <td>{format!("{} × {}", o.quantity, i18n.money(&o.add_on.price))}</td>
+                        <td class="has-text-right">{i18n.money(&o.price())}</td>
+                      </tr>
+                    }
+                })
+                .collect_view()
+        })
+    };
+
+    let discount_row = move || {
+        let discount = totals().discount;
+        (!discount.is_zero()).then(|| {
+            view! {
+              <tr>
+                <th colspan="2">{i18n.t("discount")}</th>
+                <td class="has-text-right">{format!("-{}", i18n.money(&discount))}</td>
+              </tr>
+            }
+        })
+    };
+
+    let donation_row = move || {
+        let donation = totals().donation;
+        (!donation.is_zero()).then(|| {
+            view! {
+              <tr>
+                <th colspan="2">{i18n.t("donation")}</th>
+                <td class="has-text-right">{i18n.money(&donation)}</td>
+              </tr>
+            }
+        })
+    };
+
+    view! {
+      <table class="table is-fullwidth">
+        <tbody>{ticket_rows} {add_on_rows}</tbody>
+        <tfoot>
+          <tr>
+            <th colspan="2">{i18n.t("subtotal")}</th>
+            <td class="has-text-right">{move || i18n.money(&totals().subtotal)}</td>
+          </tr>
+          {discount_row}
+          {donation_row}
+          <tr>
+            <th colspan="2">{i18n.t("total")}</th>
+            <th class="has-text-right">{move || i18n.money(&totals().total)}</th>
+          </tr>
+        </tfoot>
+      </table>
+    }
+}
@@ -0,0 +1,218 @@
+//! Everything the server needs to be told, loaded once at startup from the
+//! environment and an optional TOML file, so a missing setting stops the
+//! server starting instead of failing a booking later on.
+
+use std::path::PathBuf;
+use std::{env, fmt, fs};
+
+use crate::error::ServiceError;
+use crate::events::all_events;
+use leptos::*;
+use validator::Validate;
+
+/// Where to look for the config file when `HAPPENINGS_CONFIG` doesn't say.
+const DEFAULT_CONFIG_FILE: &str = "happenings.toml";
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub square: SquareConfig,
+    pub email: EmailConfig,
+    pub admin: AdminConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct SquareConfig {
+    /// Usually `https://` and Square's host, but can point at a local stand-in.
+    pub base_url: String,
+    pub api_key: String,
+    pub location_id: String,
+    /// The catalog item tickets are sold as.
+    pub item_id: String,
+    pub catalog_version: i64,
+    /// Webhooks are refused until this is set.
+    pub webhook_signature_key: Option<String>,
+    /// The URL Square was told to send webhooks to, which their signature covers.
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    /// What to sign in to the admin pages with; they're refused to everyone until it's set.
+    pub password: Option<String>,
+}
+
+/// Every setting that was missing or unreadable, rather than just the first.
+#[derive(Debug, Default)]
+pub struct ConfigError {
+    pub missing: Vec<String>,
+    pub invalid: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "the server isn't configured properly")?;
+        for setting in &self.missing {
+            writeln!(f, "  missing {}", setting)?;
+        }
+        for problem in &self.invalid {
+            writeln!(f, "  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl AppConfig {
+    /// Reads settings from the environment, falling back to the config file named by
+    /// `HAPPENINGS_CONFIG` (or `happenings.toml`, if there is one).
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut settings = Settings::new()?;
+
+        let endpoint = settings.required("SQUARE_ENDPOINT", "square", "endpoint");
+        let square = SquareConfig {
+            base_url: if endpoint.contains("://") {
+                endpoint
+            } else {
+                format!("https://{}", endpoint)
+            },
+            api_key: settings.required("SQUARE_API_KEY", "square", "api_key"),
+            location_id: settings.required("SQUARE_LOCATION_ID", "square", "location_id"),
+            item_id: settings.required("SQUARE_ITEM_ID", "square", "item_id"),
+            catalog_version: settings.parsed("SQUARE_CATALOG_VERSION", "square", "catalog_version"),
+            webhook_signature_key: settings.optional(
+                "SQUARE_WEBHOOK_SIGNATURE_KEY",
+                "square",
+                "webhook_signature_key",
+            ),
+            webhook_url: settings
+                .optional("SQUARE_WEBHOOK_URL", "square", "webhook_url")
+                .unwrap_or_default(),
+        };
+        let email = EmailConfig {
+            host: settings.required("EMAIL_HOST", "email", "host"),
+            port: settings.parsed("EMAIL_PORT", "email", "port"),
+            user: settings.required("EMAIL_USER", "email", "user"),
+            password: settings.required("EMAIL_PASSWORD", "email", "password"),
+        };
+        let admin = AdminConfig {
+            password: settings
+                .optional("ADMIN_PASSWORD", "admin", "password")
+                .filter(|p| !p.is_empty()),
+        };
+
+        if settings.errors.missing.is_empty() && settings.errors.invalid.is_empty() {
+            Ok(AppConfig {
+                square,
+                email,
+                admin,
+            })
+        } else {
+            Err(settings.errors)
+        }
+    }
+}
+
+/// Checks every event's checkout is set up the way Square needs, so a bad one
+/// stops the server starting instead of failing each booking for it.
+pub fn check_events() -> Result<(), ConfigError> {
+    let invalid = all_events()
+        .iter()
+        .filter_map(|event| {
+            let e = event.checkout.validate().err()?;
+            Some(format!("checkout for {} is misconfigured: {}", event.id, e))
+        })
+        .collect::<Vec<_>>();
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError {
+            invalid,
+            ..Default::default()
+        })
+    }
+}
+
+/// The config the backend provided for this request.
+pub fn app_config() -> Result<AppConfig, ServiceError> {
+    use_context::<AppConfig>().ok_or_else(|| ServiceError::config("app config wasn't provided"))
+}
+
+/// Looks settings up in the environment first, then the config file, noting
+/// anything that can't be found or read.
+struct Settings {
+    file: toml::value::Table,
+    errors: ConfigError,
+}
+
+impl Settings {
+    fn new() -> Result<Self, ConfigError> {
+        let (path, explicit) = match env::var("HAPPENINGS_CONFIG") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+
+        let file = match fs::read_to_string(&path) {
+            Ok(s) => s.parse::<toml::Value>().map_err(|e| ConfigError {
+                invalid: vec![format!("{} isn't valid TOML: {}", path.display(), e)],
+                ..Default::default()
+            })?,
+            Err(e) if explicit => {
+                return Err(ConfigError {
+                    invalid: vec![format!("couldn't read {}: {}", path.display(), e)],
+                    ..Default::default()
+                })
+            }
+            Err(_) => toml::Value::Table(Default::default()),
+        };
+
+        Ok(Settings {
+            file: file.as_table().cloned().unwrap_or_default(),
+            errors: ConfigError::default(),
+        })
+    }
+
+    fn optional(&self, var: &str, section: &str, key: &str) -> Option<String> {
+        env::var(var)
+            .ok()
+            .or_else(|| match self.file.get(section).and_then(|s| s.get(key))? {
+                toml::Value::String(s) => Some(s.clone()),
+                other => Some(other.to_string()),
+            })
+    }
+
+    fn required(&mut self, var: &str, section: &str, key: &str) -> String {
+        self.optional(var, section, key).unwrap_or_else(|| {
+            self.errors.missing.push(format!(
+                "{} (or {}.{} in the config file)",
+                var, section, key
+            ));
+            String::new()
+        })
+    }
+
+    fn parsed<T>(&mut self, var: &str, section: &str, key: &str) -> T
+    where
+        T: Default + std::str::FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.required(var, section, key);
+        if value.is_empty() {
+            return T::default();
+        }
+        value.parse().unwrap_or_else(|e| {
+            self.errors
+                .invalid
+                .push(format!("{} isn't valid ({}): {}", var, value, e));
+            T::default()
+        })
+    }
+}
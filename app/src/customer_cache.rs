@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::*;
+
+use crate::model::BookingContact;
+use crate::square_api;
+use crate::square_client::square;
+use crate::store::JsonStore;
+
+/// How long a fetched customer is trusted before we ask Square again.
+const FRESH_FOR: Duration = Duration::from_secs(15 * 60);
+/// The most customers Square will retrieve in one request.
+const BATCH_SIZE: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedCustomer {
+    contact: BookingContact,
+    fetched_at: u64,
+}
+
+impl CachedCustomer {
+    fn is_fresh(&self) -> bool { now_secs().saturating_sub(self.fetched_at) < FRESH_FOR.as_secs() }
+}
+
+/// Square customers we've already looked up, kept in memory and on disk so that
+/// listing bookings doesn't cost a Square call per order.
+///
+/// Stale entries are refreshed in a single batch, and are still served if Square
+/// is unavailable or rate limiting us.
+pub struct CustomerCache {
+    customers: JsonStore<CachedCustomer>,
+    /// IDs Square doesn't know about (e.g. old name-based IDs), so we don't keep searching for them.
+    unknown: Mutex<HashMap<String, Instant>>,
+}
+
+pub fn customer_cache() -> &'static CustomerCache {
+    static CACHE: OnceLock<CustomerCache> = OnceLock::new();
+    CACHE.get_or_init(|| CustomerCache {
+        customers: JsonStore::open("customers"),
+        unknown: Mutex::new(HashMap::new()),
+    })
+}
+
+impl CustomerCache {
+    /// Looks up contacts for the given customer IDs, fetching any that aren't
+    /// fresh in the cache. IDs that can't be resolved are left out of the result.
+    pub async fn contacts(&self, ids: &[String]) -> HashMap<String, BookingContact> {
+        let mut found = HashMap::new();
+        let mut wanted = HashSet::new();
+
+        for id in ids {
+            match self.customers.get(id) {
+                Some(cached) if cached.is_fresh() => {
+                    found.insert(id.clone(), cached.contact);
+                }
+                _ if self.known_unknown(id) => {}
+                _ => {
+                    wanted.insert(id.clone());
+                }
+            }
+        }
+
+        if wanted.is_empty() {
+            return found;
+        }
+
+        debug!(count = wanted.len(), "fetching customers from square");
+        match fetch_customers(&wanted).await {
+            Ok(fetched) => {
+                let fetched_at = now_secs();
+                let entries = fetched.iter().map(|(id, contact)| {
+                    (
+                        id.clone(),
+                        CachedCustomer {
+                            contact: contact.clone(),
+                            fetched_at,
+                        },
+                    )
+                });
+                if let Err(e) = self.customers.insert_many(entries) {
+                    warn!(error = %e, "failed to persist customer cache");
+                }
+
+                let mut unknown = self.unknown.lock().unwrap();
+                for id in wanted.iter().filter(|id| !fetched.contains_key(*id)) {
+                    warn!(id = id, "customer not found in square");
+                    unknown.insert(id.clone(), Instant::now());
+                }
+                found.extend(fetched);
+            }
+            Err(e) => {
+                warn!(error = %e, "customer fetch failed, using cached customers");
+                found.extend(
+                    wanted
+                        .iter()
+                        .filter_map(|id| self.customers.get(id).map(|c| (id.clone(), c.contact))),
+                );
+            }
+        }
+
+        found
+    }
+
+    /// Records a contact we already know the Square customer for, e.g. one we just created.
+    pub fn insert(&self, contact: BookingContact) {
+        let id = contact.id.clone();
+        let cached = CachedCustomer {
+            contact,
+            fetched_at: now_secs(),
+        };
+        if let Err(e) = self.customers.insert(id, cached) {
+            warn!(error = %e, "failed to persist customer cache");
+        }
+    }
+
+    fn known_unknown(&self, id: &str) -> bool {
+        let mut unknown = self.unknown.lock().unwrap();
+        match unknown.get(id) {
+            Some(at) if at.elapsed() < FRESH_FOR => true,
+            Some(_) => {
+                unknown.remove(id);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Retrieves just the customers in `ids` from Square, a batch at a time.
+async fn fetch_customers(ids: &HashSet<String>) -> Result<HashMap<String, BookingContact>> {
+    let ids = ids.iter().cloned().collect::<Vec<_>>();
+    let mut found = HashMap::new();
+
+    for batch in ids.chunks(BATCH_SIZE) {
+        let req = square_api::BulkRetrieveCustomersRequest {
+            customer_ids: batch.to_vec(),
+        };
+        let resp = square()
+            .map_err(|e| anyhow!("{}", e))?
+            .bulk_retrieve_customers(&req)
+            .await
+            .map_err(|e| anyhow!("customer retrieval failed: {}", e))?;
+
+        for customer in resp.responses.into_values().filter_map(|r| r.customer) {
+            let contact = contact_from_customer(&customer);
+            found.insert(contact.id.clone(), contact);
+        }
+    }
+    Ok(found)
+}
+
+/// A Square customer as a booking contact, named by whichever of their given and
+/// family names Square has.
+pub(crate) fn contact_from_customer(customer: &square_api::Customer) -> BookingContact {
+    let name = [customer.given_name.as_deref(), customer.family_name.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    BookingContact {
+        id: customer.id.clone(),
+        name,
+        email: customer.email_address.clone().unwrap_or_default(),
+        phone_no: customer.phone_number.clone().unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+fn now_secs() -> u64 { SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() }
@@ -0,0 +1,32 @@
+//! Keeps an in-progress booking in the browser's local storage, so a reload
+//! part way through the form doesn't lose what's been typed.
+
+use crate::model::NewBooking;
+use leptos::logging::*;
+use leptos::*;
+
+fn storage() -> Option<web_sys::Storage> { window().local_storage().ok().flatten() }
+
+fn key(event_id: &str) -> String { format!("happenings.draft.{}", event_id) }
+
+pub fn load_draft(event_id: &str) -> Option<NewBooking> {
+    let json = storage()?.get_item(&key(event_id)).ok().flatten()?;
+    serde_json::from_str(&json)
+        .map_err(|e| warn!("discarding unreadable draft booking: {}", e))
+        .ok()
+}
+
+pub fn save_draft(booking: &NewBooking) {
+    let (Some(storage), Ok(json)) = (storage(), serde_json::to_string(booking)) else {
+        return;
+    };
+    if storage.set_item(&key(&booking.event_id), &json).is_err() {
+        warn!("failed to save draft booking");
+    }
+}
+
+pub fn clear_draft(event_id: &str) {
+    if let Some(storage) = storage() {
+        let _ = storage.remove_item(&key(event_id));
+    }
+}
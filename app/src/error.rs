@@ -0,0 +1,207 @@
+//! What can go wrong in a server function, in a form that crosses to the browser.
+//! Users see a friendly message and a reference they can quote; the detail behind
+//! it only goes to the server's log, under the same reference.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use leptos::ServerFnError;
+
+use crate::i18n::use_i18n;
+use serde::{Deserialize, Serialize};
+
+/// What's wrong with each field of a form, keyed by the field's path, e.g.
+/// `contact.email` or `tickets.2`.
+pub type FieldErrors = BTreeMap<String, String>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ServiceError {
+    /// Something about the request the user can put right; the message says what.
+    Validation(String),
+    /// Fields of a form the user needs to correct, to be shown next to each one.
+    InvalidFields(FieldErrors),
+    /// Square, or whoever is taking payment, couldn't do what we asked.
+    PaymentProvider {
+        #[serde(skip)]
+        detail: String,
+    },
+    /// There isn't room for what was asked for.
+    Capacity(String),
+    /// Only for admins, and whoever asked hasn't signed in as one.
+    Forbidden,
+    Email {
+        #[serde(skip)]
+        detail: String,
+    },
+    Config {
+        #[serde(skip)]
+        detail: String,
+    },
+    /// Anything else, including the request never reaching the server.
+    Unexpected {
+        #[serde(skip)]
+        detail: String,
+    },
+}
+
+impl ServiceError {
+    pub fn payment(detail: impl fmt::Display) -> Self {
+        ServiceError::PaymentProvider {
+            detail: detail.to_string(),
+        }
+    }
+
+    pub fn email(detail: impl fmt::Display) -> Self {
+        ServiceError::Email {
+            detail: detail.to_string(),
+        }
+    }
+
+    pub fn config(detail: impl fmt::Display) -> Self {
+        ServiceError::Config {
+            detail: detail.to_string(),
+        }
+    }
+
+    pub fn unexpected(detail: impl fmt::Display) -> Self {
+        ServiceError::Unexpected {
+            detail: detail.to_string(),
+        }
+    }
+
+    /// What to tell the user, in their language. Validation messages are
+    /// translated where they're made, as they say what was wrong.
+    pub fn message(&self) -> String {
+        let id = match self {
+            ServiceError::Validation(message) | ServiceError::Capacity(message) => return message.clone(),
+            ServiceError::InvalidFields(_) => "error-invalid-fields",
+            ServiceError::Forbidden => "error-forbidden",
+            ServiceError::PaymentProvider { .. } => "error-payment-provider",
+            ServiceError::Email { .. } => "error-email",
+            ServiceError::Config { .. } => "error-config",
+            ServiceError::Unexpected { .. } => "error-unexpected",
+        };
+        use_i18n().t(id)
+    }
+
+    /// What's wrong with each field, if this is about a form.
+    pub fn field_errors(&self) -> Option<&FieldErrors> {
+        match self {
+            ServiceError::InvalidFields(errors) => Some(errors),
+            _ => None,
+        }
+    }
+
+    /// Whether the user is expected to fix this themselves, rather than us.
+    pub fn is_users_to_fix(&self) -> bool {
+        matches!(
+            self,
+            ServiceError::Validation(_)
+                | ServiceError::InvalidFields(_)
+                | ServiceError::Capacity(_)
+                | ServiceError::Forbidden
+        )
+    }
+}
+
+// Deliberately not `std::error::Error`, which would send it to the browser through
+// `ServerFnError`'s blanket conversion, detail and all, instead of the one below.
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::PaymentProvider { detail }
+            | ServiceError::Email { detail }
+            | ServiceError::Config { detail }
+            | ServiceError::Unexpected { detail } => write!(f, "{}: {}", self.message(), detail),
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+/// An error as the browser receives it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub error: ServiceError,
+    /// Matches the server log entry with the detail, when the error came from the server.
+    pub correlation_id: Option<String>,
+}
+
+impl From<&ServerFnError> for ErrorReport {
+    fn from(e: &ServerFnError) -> Self {
+        match e {
+            ServerFnError::ServerError(body) => serde_json::from_str(body).unwrap_or_else(|_| ErrorReport {
+                error: ServiceError::unexpected(body),
+                correlation_id: None,
+            }),
+            other => ErrorReport {
+                error: ServiceError::unexpected(other),
+                correlation_id: None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.correlation_id {
+            Some(id) if !self.error.is_users_to_fix() => {
+                let message = use_i18n().t_with(
+                    "error-with-reference",
+                    &[("message", self.error.message().into()), ("reference", id.clone().into())],
+                );
+                write!(f, "{}", message)
+            }
+            _ => write!(f, "{}", self.error.message()),
+        }
+    }
+}
+
+/// What to show the user for a failed server function.
+pub fn describe(e: &ServerFnError) -> String { ErrorReport::from(e).to_string() }
+
+/// Whether a server function was refused because the user isn't signed in as an admin.
+pub fn needs_sign_in(e: &ServerFnError) -> bool {
+    ErrorReport::from(e).error == ServiceError::Forbidden
+}
+
+#[cfg(feature = "ssr")]
+impl From<ServiceError> for ServerFnError {
+    fn from(error: ServiceError) -> Self {
+        use tracing::*;
+
+        let correlation_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+        if error.is_users_to_fix() {
+            info!(correlation_id = %correlation_id, error = ?error, "request refused");
+        } else {
+            error!(correlation_id = %correlation_id, error = %error, "server function failed");
+        }
+
+        let report = ErrorReport {
+            error,
+            correlation_id: Some(correlation_id),
+        };
+        ServerFnError::ServerError(serde_json::to_string(&report).unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::square_client::SquareError> for ServiceError {
+    fn from(e: crate::square_client::SquareError) -> Self {
+        let codes = e.codes();
+        let field = |path: &str, message: &str| {
+            ServiceError::InvalidFields(FieldErrors::from([(path.to_string(), use_i18n().t(message))]))
+        };
+        if codes.contains(&"INVALID_EMAIL_ADDRESS") {
+            field("contact.email", "square-email-rejected")
+        } else if codes.contains(&"INVALID_PHONE_NUMBER") {
+            field("contact.phone_no", "square-phone-rejected")
+        } else {
+            ServiceError::payment(e)
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<mail_send::Error> for ServiceError {
+    fn from(e: mail_send::Error) -> Self { ServiceError::email(e) }
+}
@@ -0,0 +1,37 @@
+use crate::model::*;
+use crate::money::{Currency, Money};
+use rust_decimal_macros::dec;
+
+/// The event currently taking bookings.
+pub fn current_event() -> Event {
+    let ticket_types: [TicketType; 1] = [TicketType {
+        name: "Adult".into(),
+        price: Money::new(dec!(15.00), Currency::Gbp),
+        square_item_id: "VF54IAUH3FRNQMNE7T43ZXUB".into(),
+        square_catalog_version: 1700477397626,
+        on_sale_from: None,
+        on_sale_until: None,
+        bundle: vec![],
+    }];
+
+    Event {
+        id: "xmas2023".into(),
+        name: "Little Stukeley Christmas Dinner".into(),
+        tagline: "Get your tickets for the final village event of the year!".into(),
+        ticket_types: TicketTypes::new(ticket_types),
+        add_ons: vec![],
+        donations: Donations::Fund("the village hall fund".into()),
+        checkout: CheckoutConfig {
+            description: "Little Stukeley Christmas Dinner".into(),
+            ..Default::default()
+        },
+        payment: PaymentMethod::Square,
+        currency: Currency::Gbp,
+        phone_region: "GB".into(),
+    }
+}
+
+/// Every event the site knows about.
+pub fn all_events() -> Vec<Event> { vec![current_event()] }
+
+pub fn find_event(id: &str) -> Option<Event> { all_events().into_iter().find(|e| e.id == id) }
@@ -0,0 +1,242 @@
+//! The site in the reader's language. Messages come from the Fluent files under
+//! `app/locales`, and numbers, money and dates are written the way the locale
+//! writes them. The locale is chosen once per page from what the browser asks
+//! for, and provided to every component below the app.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{accepted_languages, negotiate_languages, NegotiationStrategy};
+use leptos::*;
+use rust_decimal::Decimal;
+use unic_langid::LanguageIdentifier;
+
+use crate::model::{DiscountCode, DiscountKind};
+use crate::money::{Currency, Money, Totals};
+
+/// The site was written in British English, so it has every message, and is
+/// what's used when nothing the browser asks for is available.
+pub const DEFAULT_LOCALE: &str = "en-GB";
+
+/// A locale's translations, and how it writes numbers and dates.
+struct LocaleData {
+    id: &'static str,
+    messages: &'static str,
+    decimal_separator: &'static str,
+    group_separator: &'static str,
+    /// Whether the currency symbol follows the amount, as in `15,00 €`.
+    symbol_after: bool,
+    dates: chrono::Locale,
+}
+
+static LOCALES: [LocaleData; 2] = [
+    LocaleData {
+        id: "en-GB",
+        messages: include_str!("../locales/en-GB/main.ftl"),
+        decimal_separator: ".",
+        group_separator: ",",
+        symbol_after: false,
+        dates: chrono::Locale::en_GB,
+    },
+    LocaleData {
+        id: "fr-FR",
+        messages: include_str!("../locales/fr-FR/main.ftl"),
+        decimal_separator: ",",
+        group_separator: "\u{202f}",
+        symbol_after: true,
+        dates: chrono::Locale::fr_FR,
+    },
+];
+
+fn bundles() -> &'static HashMap<&'static str, FluentBundle<FluentResource>> {
+    static BUNDLES: OnceLock<HashMap<&'static str, FluentBundle<FluentResource>>> = OnceLock::new();
+    BUNDLES.get_or_init(|| {
+        LOCALES
+            .iter()
+            .map(|locale| {
+                let id = locale.id.parse::<LanguageIdentifier>().expect("locale ids to be valid");
+                let resource = FluentResource::try_new(locale.messages.to_string())
+                    .unwrap_or_else(|(_, errors)| panic!("{} messages don't parse: {:?}", locale.id, errors));
+                let mut bundle = FluentBundle::new_concurrent(vec![id]);
+                // The marks Fluent puts around arguments would end up in emails and attributes.
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .unwrap_or_else(|errors| panic!("{} messages are repeated: {:?}", locale.id, errors));
+                (locale.id, bundle)
+            })
+            .collect()
+    })
+}
+
+/// Messages and formatting for one locale.
+#[derive(Clone, Copy)]
+pub struct I18n {
+    locale: &'static LocaleData,
+}
+
+impl Default for I18n {
+    fn default() -> Self { I18n::new(DEFAULT_LOCALE) }
+}
+
+impl I18n {
+    /// The closest locale we have to `locale`, e.g. `fr-FR` for `fr-CA`.
+    pub fn new(locale: &str) -> Self { I18n::negotiate(&[locale]) }
+
+    /// The best of the locales we have for someone who reads `requested`, in order of preference.
+    pub fn negotiate<S: AsRef<str>>(requested: &[S]) -> Self {
+        let requested = requested
+            .iter()
+            .filter_map(|r| r.as_ref().parse::<LanguageIdentifier>().ok())
+            .collect::<Vec<_>>();
+        I18n::best_of(&requested)
+    }
+
+    /// The best of the locales we have for a browser's `Accept-Language` header.
+    pub fn from_accept_language(header: &str) -> Self { I18n::best_of(&accepted_languages::parse(header)) }
+
+    fn best_of(requested: &[LanguageIdentifier]) -> Self {
+        let available = LOCALES
+            .iter()
+            .map(|l| l.id.parse::<LanguageIdentifier>().expect("locale ids to be valid"))
+            .collect::<Vec<_>>();
+        let chosen = negotiate_languages(requested, &available, Some(&available[0]), NegotiationStrategy::Lookup)
+            .first()
+            .map(|id| id.to_string());
+        let locale = LOCALES
+            .iter()
+            .find(|l| Some(l.id) == chosen.as_deref())
+            .unwrap_or(&LOCALES[0]);
+        I18n { locale }
+    }
+
+    /// The locale's id, for the page's `lang`.
+    pub fn lang(&self) -> &'static str { self.locale.id }
+
+    /// The message called `id`.
+    pub fn t(&self, id: &str) -> String { self.format(id, None) }
+
+    /// The message called `id`, filled in with `args`.
+    pub fn t_with(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let args = args.iter().cloned().collect::<FluentArgs>();
+        self.format(id, Some(&args))
+    }
+
+    /// Falls back to English for anything that hasn't been translated yet, and to
+    /// the message's id if even that's missing, so a gap shows rather than breaks.
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        let bundles = bundles();
+        let found = [self.locale.id, DEFAULT_LOCALE].into_iter().find_map(|locale| {
+            let bundle = &bundles[locale];
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = vec![];
+            let message = bundle.format_pattern(pattern, args, &mut errors).to_string();
+            if !errors.is_empty() {
+                logging::warn!("problems formatting {} in {}: {:?}", id, locale, errors);
+            }
+            Some(message)
+        });
+        found.unwrap_or_else(|| {
+            logging::warn!("no message called {}", id);
+            id.to_string()
+        })
+    }
+
+    /// `amount` to `places` decimal places, with the locale's separators.
+    pub fn number(&self, amount: Decimal, places: u32) -> String {
+        let rounded = amount.round_dp(places);
+        let digits = format!("{:.*}", places as usize, rounded.abs());
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits.as_str(), ""));
+
+        let mut formatted = String::new();
+        if rounded.is_sign_negative() && !rounded.is_zero() {
+            formatted.push('-');
+        }
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                formatted.push_str(self.locale.group_separator);
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push_str(self.locale.decimal_separator);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// An amount with its currency's symbol, e.g. `£15.00` or `15,00 €`.
+    pub fn money(&self, money: &Money) -> String {
+        let number = self.number(money.amount.abs(), money.currency.minor_units());
+        let sign = if money.amount.is_sign_negative() && !money.round().is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        let symbol = money.currency.symbol();
+        if self.locale.symbol_after {
+            format!("{}{}\u{a0}{}", sign, number, symbol)
+        } else {
+            format!("{}{}{}", sign, symbol, number)
+        }
+    }
+
+    /// Totals in several currencies, e.g. `£7.00 + €3.00`.
+    pub fn totals(&self, totals: &Totals) -> String {
+        let totals = totals.iter().map(|m| self.money(m)).collect::<Vec<_>>();
+        if totals.is_empty() {
+            return self.money(&Money::zero(Currency::default()));
+        }
+        totals.join(" + ")
+    }
+
+    /// A day, e.g. `24 December 2023`.
+    pub fn date(&self, at: DateTime<Utc>) -> String { at.format_localized("%-d %B %Y", self.locale.dates).to_string() }
+
+    /// What a discount code takes off, e.g. `10% off`.
+    pub fn discount(&self, discount: &DiscountCode) -> String {
+        match &discount.kind {
+            DiscountKind::Percentage(pct) => self.t_with(
+                "discount-percentage",
+                &[("percent", pct.normalize().to_string().into())],
+            ),
+            DiscountKind::Fixed(amount) => self.t_with("discount-fixed", &[("amount", self.money(amount).into())]),
+        }
+    }
+}
+
+/// Works out which locale to show the page in, and provides it to the components below.
+pub fn provide_i18n() { provide_context(requested_locale()); }
+
+/// The locale the page is being shown in. Server functions, which aren't below the
+/// app, get the locale the browser asked for with the request.
+pub fn use_i18n() -> I18n { use_context::<I18n>().unwrap_or_else(requested_locale) }
+
+#[cfg(feature = "ssr")]
+fn requested_locale() -> I18n {
+    use_context::<http::request::Parts>()
+        .and_then(|parts| {
+            let header = parts.headers.get(http::header::ACCEPT_LANGUAGE)?;
+            header.to_str().ok().map(I18n::from_accept_language)
+        })
+        .unwrap_or_default()
+}
+
+/// The server has already chosen, and said so on the page, so the browser sticks
+/// to it; otherwise the rendered and hydrated page could disagree.
+#[cfg(not(feature = "ssr"))]
+fn requested_locale() -> I18n {
+    let chosen = document()
+        .document_element()
+        .and_then(|html| html.get_attribute("lang"));
+    match chosen {
+        Some(lang) if !lang.is_empty() => I18n::new(&lang),
+        _ => {
+            let languages = window().navigator().languages();
+            I18n::negotiate(&languages.iter().filter_map(|l| l.as_string()).collect::<Vec<_>>())
+        }
+    }
+}
@@ -2,27 +2,49 @@ use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
+use crate::components::provide_ids;
+use crate::i18n::{provide_i18n, use_i18n};
+
 pub mod components;
+#[cfg(feature = "ssr")]
+pub mod config;
+#[cfg(feature = "ssr")]
+pub mod customer_cache;
+pub mod draft;
+pub mod error;
 pub mod error_template;
+pub mod events;
+pub mod i18n;
 pub mod model;
+pub mod money;
 mod pages;
+#[cfg(feature = "ssr")]
+pub mod payments;
 pub mod reactive_list;
 pub mod server_fns;
 pub mod square_api;
+#[cfg(feature = "ssr")]
+pub mod square_client;
+#[cfg(feature = "ssr")]
+pub mod store;
 pub mod utils;
 
-pub use pages::{EmailBooking, ListBookings, NewBooking};
+pub use pages::{BookingConfirmed, Contacts, Discounts, EmailBooking, ListBookings, NewBooking};
 
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    provide_i18n();
+    provide_ids();
+    let i18n = use_i18n();
 
     view! {
+      <Html lang=i18n.lang()/>
       <Stylesheet id="leptos" href="/pkg/happenings.css"/>
 
       // sets the document title
-      <Title text="Stukeley Happenings"/>
+      <Title text=i18n.t("site-title")/>
 
       // content for this welcome page
       <Router>
@@ -42,6 +64,13 @@ pub fn App() -> impl IntoView {
               }
             />
 
+            <Route
+              path="/booked"
+              view=|| {
+                  view! { <BookingConfirmed/> }
+              }
+            />
+
             <Route
               path="/bookings"
               view=|| {
@@ -49,6 +78,20 @@ pub fn App() -> impl IntoView {
               }
             />
 
+            <Route
+              path="/contacts"
+              view=|| {
+                  view! { <Contacts/> }
+              }
+            />
+
+            <Route
+              path="/discounts"
+              view=|| {
+                  view! { <Discounts/> }
+              }
+            />
+
             <Route
               path="/email-booking"
               view=|| {
@@ -1,15 +1,56 @@
-use phonenumber::{country, parse, ParseError, PhoneNumber};
+use crate::i18n::I18n;
+use crate::money::{Currency, Money, Totals};
+use chrono::{DateTime, Utc};
+use phonenumber::{country, parse, Mode, ParseError, PhoneNumber};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use validator::{Validate, ValidationError};
+use std::collections::BTreeMap;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TicketType {
     pub name: String,
-    pub price: Decimal,
+    pub price: Money,
     pub square_item_id: String,
     pub square_catalog_version: i64,
+    /// When this ticket type goes on sale, if not straight away.
+    #[serde(default)]
+    pub on_sale_from: Option<DateTime<Utc>>,
+    /// When this ticket type stops being sold, e.g. the end of an early bird offer.
+    #[serde(default)]
+    pub on_sale_until: Option<DateTime<Utc>>,
+    /// For bundles (e.g. a family ticket), who each of the included tickets is for.
+    /// Square only has room in a line item's metadata for up to nine.
+    #[serde(default)]
+    pub bundle: Vec<String>,
+}
+
+impl TicketType {
+    pub fn is_on_sale(&self, now: DateTime<Utc>) -> bool {
+        self.on_sale_from.map_or(true, |from| from <= now) && self.on_sale_until.map_or(true, |until| now < until)
+    }
+
+    pub fn is_bundle(&self) -> bool { !self.bundle.is_empty() }
+
+    /// Whether `group` is one whole bundle of this type: a ticket of this type for
+    /// each of the bundle's members, and nothing else.
+    pub fn is_made_up_of(&self, group: &[&Ticket]) -> bool {
+        let Some(mut labels) = group
+            .iter()
+            .map(|t| t.bundle.as_ref().map(|b| b.label.as_str()))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return false;
+        };
+        let mut members = self.bundle.iter().map(String::as_str).collect::<Vec<_>>();
+        labels.sort_unstable();
+        members.sort_unstable();
+        self.is_bundle()
+            && group.len() == self.bundle.len()
+            && group.iter().all(|t| t.ticket_type.name == self.name)
+            && labels == members
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -25,7 +66,16 @@ impl TicketTypes {
         self.0.into_iter().find(|tt| tt.name == tt_name)
     }
 
-    pub fn standard(&self) -> Option<TicketType> { self.0.first().cloned() }
+    pub fn standard(&self) -> Option<TicketType> { self.0.iter().find(|tt| !tt.is_bundle()).cloned() }
+
+    /// The ticket types that can be bought at `now`.
+    pub fn on_sale(&self, now: DateTime<Utc>) -> TicketTypes {
+        TicketTypes(self.0.iter().filter(|tt| tt.is_on_sale(now)).cloned().collect())
+    }
+
+    pub fn singles(&self) -> TicketTypes { TicketTypes(self.0.iter().filter(|tt| !tt.is_bundle()).cloned().collect()) }
+
+    pub fn bundles(&self) -> TicketTypes { TicketTypes(self.0.iter().filter(|tt| tt.is_bundle()).cloned().collect()) }
 }
 
 impl IntoIterator for TicketTypes {
@@ -34,12 +84,79 @@ impl IntoIterator for TicketTypes {
     fn into_iter(self) -> Self::IntoIter { self.0.into_iter() }
 }
 
+/// Something sold alongside tickets, e.g. raffle strips, wine or a lift home.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AddOn {
+    pub name: String,
+    pub price: Money,
+    pub square_item_id: String,
+    pub square_catalog_version: i64,
+    /// The most one booking can buy, if there's a limit.
+    #[serde(default)]
+    pub max_per_booking: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AddOnOrder {
+    pub add_on: AddOn,
+    pub quantity: u32,
+}
+
+impl AddOnOrder {
+    pub fn price(&self) -> Money { self.add_on.price * self.quantity }
+}
+
+/// How an event lets bookers give a little extra.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Donations {
+    #[default]
+    None,
+    /// Square asks for a tip on the checkout page.
+    Tipping,
+    /// We ask for a donation to the named fund on the booking form.
+    Fund(String),
+}
+
+/// How Square's checkout page is set up for an event.
+#[derive(Clone, Debug, Default, Validate)]
+pub struct CheckoutConfig {
+    /// Shown on the payment link; Square requires one.
+    #[validate(length(min = 1, max = 4096))]
+    pub description: String,
+    /// Where Square sends the booker once they've paid.
+    #[validate(url)]
+    pub redirect_url: Option<String>,
+    pub enable_coupon: bool,
+    pub enable_loyalty: bool,
+    pub ask_for_shipping_address: bool,
+    #[validate(email)]
+    pub support_email: Option<String>,
+}
+
+/// How bookers pay for an event.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PaymentMethod {
+    /// Online, through a Square payment link.
+    #[default]
+    Square,
+    /// On the door, or by invoice; the booking is made without paying.
+    OnTheDoor,
+}
+
 #[derive(Clone, Debug)]
 pub struct Event {
     pub id: String,
     pub name: String,
     pub tagline: String,
     pub ticket_types: TicketTypes,
+    pub add_ons: Vec<AddOn>,
+    pub donations: Donations,
+    pub checkout: CheckoutConfig,
+    pub payment: PaymentMethod,
+    /// What the event's tickets, add-ons and donations are priced in.
+    pub currency: Currency,
+    /// Where most bookers' phones are from, as an ISO 3166 code like `GB`.
+    pub phone_region: String,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, Validate)]
@@ -49,37 +166,143 @@ pub struct BookingContact {
     pub name: String,
     #[validate(email)]
     pub email: String,
+    /// In E.164 form, e.g. `+447700900123`, once it's been entered properly.
     #[validate(length(min = 1), custom = "validate_phone_no")]
     pub phone_no: String,
+    /// The country the phone number was entered for, as an ISO 3166 code.
+    #[serde(default)]
+    pub phone_region: String,
     pub event_id: String,
+    #[serde(default)]
+    pub marketing_consent: bool,
 }
 
 impl BookingContact {
     pub fn phone_number(&self) -> Result<PhoneNumber, ParseError> {
-        parse(Some(country::Id::GB), self.phone_no.clone())
+        parse(Some(phone_region(&self.phone_region)), self.phone_no.clone())
+    }
+
+    /// Puts the phone number in E.164 form, if it's valid for the region it was entered for.
+    pub fn normalise_phone(&mut self) {
+        if let Some(e164) = to_e164(&self.phone_no, &self.phone_region) {
+            self.phone_no = e164;
+        }
     }
 }
 
-fn validate_phone_no(phone_str: &str) -> Result<(), ValidationError> {
-    match parse(Some(country::Id::GB), phone_str) {
-        Ok(pn) => {
-            if pn.is_valid() {
-                Ok(())
-            } else {
-                Err(ValidationError::new("Invalid phone number"))
-            }
+/// The phone region assumed when none has been chosen, or it isn't one we know.
+pub const DEFAULT_PHONE_REGION: country::Id = country::Id::GB;
+
+/// The countries offered for phone numbers, by ISO 3166 code; their names are
+/// translated as `country-<code>`. Numbers from anywhere else can still be
+/// entered starting with `+` and their country code.
+pub const PHONE_REGIONS: [&str; 16] = [
+    "GB", "IE", "FR", "DE", "NL", "BE", "ES", "PT", "IT", "PL", "US", "CA", "AU", "NZ", "IN", "ZA",
+];
+
+pub fn phone_region(code: &str) -> country::Id { code.parse().unwrap_or(DEFAULT_PHONE_REGION) }
+
+/// A phone number as entered for a region, in E.164 form, if it's one we could ring.
+pub fn to_e164(phone_str: &str, region: &str) -> Option<String> {
+    parse(Some(phone_region(region)), phone_str)
+        .ok()
+        .filter(|pn| pn.is_valid())
+        .map(|pn| pn.format().mode(Mode::E164).to_string())
+}
+
+/// An attendee's name is optional, but if it's given it has to look like a name.
+fn validate_attendee_name(name: &str) -> Result<(), ValidationError> {
+    let name = name.trim();
+    let problem = if name.chars().count() > 40 {
+        Some("attendee-name-too-long")
+    } else if !name.is_empty() && name.chars().filter(|c| c.is_alphabetic()).count() < 2 {
+        Some("attendee-name-invalid")
+    } else {
+        None
+    };
+    match problem {
+        None => Ok(()),
+        Some(message) => {
+            let mut err = ValidationError::new("attendee_name");
+            err.message = Some(message.into());
+            Err(err)
         }
-        Err(_) => Err(ValidationError::new("Invalid phone number")),
+    }
+}
+
+/// A message for each field that failed validation, keyed by field name. The
+/// messages given to the validator are the ids of translated messages.
+pub fn validation_messages(errors: &ValidationErrors, i18n: &I18n) -> BTreeMap<String, String> {
+    errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errs)| {
+            let message = errs
+                .iter()
+                .find_map(|e| e.message.as_ref().map(|m| i18n.t(m)))
+                .unwrap_or_else(|| i18n.t_with("check-field", &[("field", field.replace('_', " ").into())]));
+            (field.to_string(), message)
+        })
+        .collect()
+}
+
+/// A booking's phone number is required, has to be one we could ring, and is kept
+/// in E.164 form so it means the same thing whichever country it's from.
+pub fn validate_phone_no(phone_str: &str) -> Result<(), ValidationError> {
+    if phone_str.starts_with('+') && to_e164(phone_str, "").as_deref() == Some(phone_str) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("Invalid phone number"))
     }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, Validate)]
 pub struct NewBooking {
+    /// Made when the booker starts filling in the form, and kept with their draft,
+    /// so resubmitting the same booking is recognised as a repeat.
+    #[serde(default)]
+    pub draft_id: String,
     pub event_id: String,
     #[validate]
     pub contact: BookingContact,
     #[serde(default)]
+    #[validate]
     pub tickets: Vec<Ticket>,
+    #[serde(default)]
+    pub add_ons: Vec<AddOnOrder>,
+    /// In the event's currency, which makes it the booking's currency too, even when it's nothing.
+    #[serde(default)]
+    pub donation: Money,
+    /// The discount as shown to the booker; the server looks the code up again before applying it.
+    #[serde(default)]
+    pub discount: Option<DiscountCode>,
+}
+
+impl NewBooking {
+    pub fn totals(&self) -> OrderTotals {
+        let currency = self.donation.currency;
+        let subtotal = price_of(&self.tickets, currency) + price_of_add_ons(&self.add_ons, currency);
+        let discount = self
+            .discount
+            .as_ref()
+            .map(|d| d.amount_off(subtotal))
+            .unwrap_or(Money::zero(currency));
+        OrderTotals {
+            subtotal,
+            discount,
+            donation: self.donation,
+            total: subtotal - discount + self.donation,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderTotals {
+    pub subtotal: Money,
+    pub discount: Money,
+    /// Donations aren't discounted, so they're added after the discount.
+    pub donation: Money,
+    pub total: Money,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -88,6 +311,11 @@ pub struct Booking {
     pub event_id: String,
     pub contact: BookingContact,
     pub tickets: Vec<Ticket>,
+    #[serde(default)]
+    pub add_ons: Vec<AddOnOrder>,
+    /// Donations and tips given with the booking, in the booking's currency.
+    #[serde(default)]
+    pub donation: Money,
     pub payment: BookingPayment,
 }
 
@@ -95,8 +323,8 @@ pub struct Booking {
 pub enum BookingPayment {
     #[default]
     NotPaid,
-    Cash(Decimal),
-    Card(Decimal),
+    Cash(Money),
+    Card(Money),
 }
 
 impl BookingContact {
@@ -112,17 +340,36 @@ impl BookingContact {
             email: email.into(),
             event_id: event_id.into(),
             phone_no: "".into(),
+            phone_region: "".into(),
+            marketing_consent: false,
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Validate)]
 pub struct Ticket {
     pub booking_id: String,
     pub ticket_type: TicketType,
+    /// Who the ticket is for, if the booker wants to say.
+    #[serde(default)]
+    #[validate(custom = "validate_attendee_name")]
+    pub attendee_name: String,
     pub vegetarian: bool,
     pub gluten_free: bool,
+    /// Kept short, as it's sent to Square as line item metadata. A bundle keeps all
+    /// of a ticket's details in one metadata value, so those are checked together.
+    #[validate(length(max = 100, message = "dietary-too-long"))]
     pub dietary_requirements: String,
+    /// Set when this ticket is one of several bought together as a bundle.
+    #[serde(default)]
+    pub bundle: Option<BundleMember>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BundleMember {
+    pub bundle_id: String,
+    /// Who this ticket in the bundle is for, e.g. "Child".
+    pub label: String,
 }
 
 impl Ticket {
@@ -130,10 +377,186 @@ impl Ticket {
         Self {
             booking_id,
             ticket_type: tt,
+            attendee_name: "".into(),
             vegetarian: false,
             gluten_free: false,
             dietary_requirements: "".into(),
+            bundle: None,
+        }
+    }
+
+    /// The tickets making up one bundle of type `tt`.
+    pub fn bundle(booking_id: String, tt: TicketType) -> Vec<Self> {
+        let bundle_id = Uuid::new_v4().to_string();
+        tt.bundle
+            .iter()
+            .map(|label| Self {
+                bundle: Some(BundleMember {
+                    bundle_id: bundle_id.clone(),
+                    label: label.clone(),
+                }),
+                ..Self::new(booking_id.clone(), tt.clone())
+            })
+            .collect()
+    }
+
+    pub fn dietary(&self) -> DietaryPreferences {
+        DietaryPreferences {
+            vegetarian: self.vegetarian,
+            gluten_free: self.gluten_free,
+            requirements: match self.dietary_requirements.as_str() {
+                "none" => "".to_string(),
+                reqs => reqs.to_string(),
+            },
+        }
+    }
+}
+
+/// Groups tickets into the items they're charged as: a ticket on its own, or all
+/// the tickets in a bundle.
+pub fn ticket_groups(tickets: &[Ticket]) -> Vec<Vec<&Ticket>> {
+    let mut groups: Vec<Vec<&Ticket>> = vec![];
+    for ticket in tickets {
+        let bundle_id = ticket.bundle.as_ref().map(|b| &b.bundle_id);
+        let existing = bundle_id.and_then(|id| {
+            groups
+                .iter()
+                .position(|g| g[0].bundle.as_ref().map(|b| &b.bundle_id) == Some(id))
+        });
+        match existing {
+            Some(i) => groups[i].push(ticket),
+            None => groups.push(vec![ticket]),
+        }
+    }
+    groups
+}
+
+fn price_of(tickets: &[Ticket], currency: Currency) -> Money {
+    ticket_groups(tickets)
+        .iter()
+        .fold(Money::zero(currency), |a, g| a + g[0].ticket_type.price)
+}
+
+fn price_of_add_ons(add_ons: &[AddOnOrder], currency: Currency) -> Money {
+    add_ons.iter().fold(Money::zero(currency), |a, o| a + o.price())
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct DietaryPreferences {
+    pub vegetarian: bool,
+    pub gluten_free: bool,
+    pub requirements: String,
+}
+
+impl Booking {
+    pub fn currency(&self) -> Currency { self.donation.currency }
+
+    pub fn price(&self) -> Money {
+        price_of(&self.tickets, self.currency()) + price_of_add_ons(&self.add_ons, self.currency()) + self.donation
+    }
+
+    pub fn paid(&self) -> Money {
+        match self.payment {
+            BookingPayment::NotPaid => Money::zero(self.currency()),
+            BookingPayment::Card(amt) | BookingPayment::Cash(amt) => amt,
         }
     }
 }
 
+/// Someone who has booked with us, remembered across events.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Contact {
+    /// The Square customer ID.
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub phone_no: String,
+    pub marketing_consent: bool,
+    pub dietary: DietaryPreferences,
+    /// Customer IDs of duplicate contacts that have been merged into this one.
+    #[serde(default)]
+    pub merged_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContactSummary {
+    pub contact: Contact,
+    pub bookings: Vec<Booking>,
+    /// What they've paid, in each currency they've paid in.
+    pub total_spend: Totals,
+    /// Other contacts sharing this contact's email address or phone number.
+    pub possible_duplicates: Vec<String>,
+}
+
+/// The details we remember about someone who has booked before, for prefilling the booking form.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PreviousBooking {
+    pub name: String,
+    pub email: String,
+    pub phone_no: String,
+    pub marketing_consent: bool,
+    pub dietary: DietaryPreferences,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum DiscountKind {
+    Percentage(Decimal),
+    Fixed(Money),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DiscountCode {
+    pub code: String,
+    pub event_id: String,
+    pub kind: DiscountKind,
+    pub max_uses: Option<u32>,
+    /// Bookings paid for with the code.
+    #[serde(default)]
+    pub uses: u32,
+    /// Uses set aside for checkouts that haven't been paid for yet.
+    #[serde(default)]
+    pub held: u32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl DiscountCode {
+    pub fn normalise(code: &str) -> String { code.trim().to_uppercase() }
+
+    pub fn amount_off(&self, subtotal: Money) -> Money {
+        match self.kind {
+            DiscountKind::Percentage(pct) => {
+                Money::new(subtotal.amount * pct / Decimal::ONE_HUNDRED, subtotal.currency).round()
+            }
+            DiscountKind::Fixed(amount) => Money::new(amount.amount.min(subtotal.amount), subtotal.currency),
+        }
+    }
+
+    /// Checks the discount is one that can be given on `event`, or gives the id of
+    /// the message saying why not.
+    pub fn check_terms(&self, event: &Event) -> Result<(), &'static str> {
+        match &self.kind {
+            DiscountKind::Percentage(pct) if *pct < Decimal::ZERO || *pct > Decimal::ONE_HUNDRED => {
+                Err("discount-percentage-range")
+            }
+            DiscountKind::Fixed(amount) if amount.amount <= Decimal::ZERO => Err("discount-amount-positive"),
+            DiscountKind::Fixed(amount) if amount.currency != event.currency => Err("discount-currency"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the code can be used for a booking on `event` right now, or
+    /// gives the id of the message saying why not.
+    pub fn check(&self, event: &Event, now: DateTime<Utc>) -> Result<(), &'static str> {
+        if self.event_id != event.id {
+            return Err("discount-wrong-event");
+        }
+        self.check_terms(event)?;
+        if self.expires_at.is_some_and(|at| at < now) {
+            return Err("discount-expired");
+        }
+        if self.max_uses.is_some_and(|max| self.uses + self.held >= max) {
+            return Err("discount-used-up");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+//! Amounts of money and the currencies they're in.
+
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// The currencies events can be priced in, named by their ISO 4217 codes as Square expects.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Gbp,
+    Eur,
+    Usd,
+    Jpy,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Gbp => "GBP",
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::Gbp => "£",
+            Currency::Eur => "€",
+            Currency::Usd => "$",
+            Currency::Jpy => "¥",
+        }
+    }
+
+    /// How many decimal places the currency's smallest unit is, e.g. 2 for pence.
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+}
+
+/// An amount in a particular currency. Amounts only add up within one currency;
+/// mixing them is a bug, so the arithmetic below panics rather than guess.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "MoneyRepr")]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: Currency,
+}
+
+/// Money as it's been stored: either with its currency, or as a bare amount from
+/// before there was more than one, which was always pounds.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneyRepr {
+    Money { amount: Decimal, currency: Currency },
+    Amount(Decimal),
+}
+
+impl From<MoneyRepr> for Money {
+    fn from(repr: MoneyRepr) -> Self {
+        match repr {
+            MoneyRepr::Money { amount, currency } => Money::new(amount, currency),
+            MoneyRepr::Amount(amount) => Money::new(amount, Currency::Gbp),
+        }
+    }
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self { Money { amount, currency } }
+
+    pub fn zero(currency: Currency) -> Self { Money::new(Decimal::ZERO, currency) }
+
+    /// From a count of the currency's smallest unit, e.g. pence, as Square gives amounts.
+    pub fn from_minor_units(amount: i64, currency: Currency) -> Self {
+        Money::new(Decimal::new(amount, currency.minor_units()), currency)
+    }
+
+    /// As a count of the currency's smallest unit, rounding away anything smaller.
+    pub fn to_minor_units(&self) -> i64 {
+        let scale = Decimal::from(10_i64.pow(self.currency.minor_units()));
+        (self.amount * scale).round().to_i64().unwrap_or_default()
+    }
+
+    /// Rounded to the currency's smallest unit.
+    pub fn round(&self) -> Self {
+        Money::new(
+            self.amount.round_dp(self.currency.minor_units()),
+            self.currency,
+        )
+    }
+
+    pub fn is_zero(&self) -> bool { self.amount.is_zero() }
+
+    fn same_currency(&self, other: &Money) {
+        assert_eq!(
+            self.currency,
+            other.currency,
+            "can't combine {} with {}",
+            self.currency.code(),
+            other.currency.code()
+        );
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.same_currency(&other);
+        Money::new(self.amount + other.amount, self.currency)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.same_currency(&other);
+        Money::new(self.amount - other.amount, self.currency)
+    }
+}
+
+impl Mul<u32> for Money {
+    type Output = Money;
+
+    fn mul(self, quantity: u32) -> Money {
+        Money::new(self.amount * Decimal::from(quantity), self.currency)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.amount.is_sign_negative() && !self.amount.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        let places = self.currency.minor_units() as usize;
+        write!(
+            f,
+            "{}{}{:.*}",
+            sign,
+            self.currency.symbol(),
+            places,
+            self.amount.abs()
+        )
+    }
+}
+
+/// Money added up across bookings that may not all be in the same currency, with
+/// a total for each.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Totals(Vec<Money>);
+
+impl Totals {
+    pub fn push(&mut self, money: Money) {
+        match self.0.iter_mut().find(|t| t.currency == money.currency) {
+            Some(total) => *total = *total + money,
+            None => self.0.push(money),
+        }
+    }
+
+    /// The total in each currency, in the order the currencies were first seen.
+    pub fn iter(&self) -> impl Iterator<Item = &Money> { self.0.iter() }
+}
+
+impl FromIterator<Money> for Totals {
+    fn from_iter<I: IntoIterator<Item = Money>>(iter: I) -> Self {
+        let mut totals = Totals::default();
+        for money in iter {
+            totals.push(money);
+        }
+        totals
+    }
+}
+
+impl fmt::Display for Totals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "{}", Money::zero(Currency::default()));
+        }
+        let totals = self.0.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", totals.join(" + "))
+    }
+}
@@ -0,0 +1,23 @@
+use crate::events::current_event;
+use crate::i18n::use_i18n;
+use leptos::*;
+use leptos_router::*;
+
+/// Where bookers land after booking an event that's paid for on the door.
+#[component]
+pub fn BookingConfirmed() -> impl IntoView {
+    let i18n = use_i18n();
+    let query = use_query_map();
+    let order_id = move || query.with(|q| q.get("order").cloned().unwrap_or_default());
+
+    view! {
+      <section class="section">
+        <div class="container">
+          <h1 class="title">{current_event().name}</h1>
+          <p class="subtitle">{i18n.t("booked-in")}</p>
+          <div class="block">{i18n.t("pay-on-the-door")}</div>
+          <div class="block">{i18n.t("booking-reference")} " " <b>{order_id}</b></div>
+        </div>
+      </section>
+    }
+}
@@ -1,15 +1,24 @@
-// <Modal
-// active=modal_active
-// close_requested=move || set_modal_active(false)
-// title="Booking Summary"
-// footer=move || {
-//     view! {
-//       <button class="button is-success">Save changes</button>
-//       <button class="button">Cancel</button>
-//     }
-// }
-// >
+use crate::components::*;
+use crate::i18n::use_i18n;
+use crate::model::*;
+use leptos::*;
 
-// <BookingSummary booking=booking_contact tickets=tickets/>
-// </Modal>
+/// Everything about a booking, for the booker to check before they pay.
+#[component]
+pub fn BookingSummary(#[prop(into)] booking: Signal<NewBooking>) -> impl IntoView {
+    let i18n = use_i18n();
+    let contact = move || booking.with(|b| b.contact.clone());
 
+    view! {
+      <div class="block">
+        <h2 class="subtitle">{i18n.t("booking-contact")}</h2>
+        <p>{move || contact().name}</p>
+        <p>{move || contact().email}</p>
+        <p>{move || contact().phone_no}</p>
+      </div>
+      <div class="block">
+        <h2 class="subtitle">{i18n.t("tickets")}</h2>
+        <OrderSummary booking=booking/>
+      </div>
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::components::AdminSignIn;
+use crate::error::{describe, needs_sign_in};
+use crate::i18n::{use_i18n, I18n};
+use crate::model::*;
+use crate::server_fns::{list_contacts, merge_contacts, save_contact};
+use leptos::*;
+
+#[component]
+pub fn Contacts() -> impl IntoView {
+    let i18n = use_i18n();
+    let merge = create_action(|(keep, duplicate): &(String, String)| {
+        let (keep, duplicate) = (keep.clone(), duplicate.clone());
+        async move { merge_contacts(keep, duplicate).await }
+    });
+    let save = create_action(|contact: &Contact| {
+        let contact = contact.clone();
+        async move { save_contact(contact).await }
+    });
+
+    let signed_in = create_trigger();
+
+    let contacts = create_resource(
+        move || {
+            signed_in.track();
+            (merge.version().get(), save.version().get())
+        },
+        |_| async move { list_contacts().await },
+    );
+
+    view! {
+      <section class="section">
+        <div class="container">
+          <h1 class="title">{i18n.t("contacts")}</h1>
+          <Suspense fallback=move || {
+              view! { <p>{i18n.t("loading")}</p> }
+          }>
+
+            {move || match contacts.get() {
+                None => view! { <p>{i18n.t("loading")}</p> }.into_view(),
+                Some(Err(e)) if needs_sign_in(&e) => {
+                    view! { <AdminSignIn signed_in=move || signed_in.notify()/> }.into_view()
+                }
+                Some(Err(e)) => {
+                    view! { <p>{i18n.t_with("contacts-failed", &[("error", describe(&e).into())])}</p> }.into_view()
+                }
+                Some(Ok(cs)) => view! { <ContactsTable contacts=cs merge=merge save=save/> }.into_view(),
+            }}
+
+          </Suspense>
+        </div>
+      </section>
+    }
+}
+
+#[component]
+fn ContactsTable(
+    contacts: Vec<ContactSummary>,
+    merge: Action<(String, String), Result<(), ServerFnError>>,
+    save: Action<Contact, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let by_id = store_value(
+        contacts
+            .iter()
+            .map(|s| (s.contact.id.clone(), s.contact.clone()))
+            .collect::<HashMap<_, _>>(),
+    );
+
+    view! {
+      <table class="table">
+        <thead>
+          <tr>
+            <th>{i18n.t("name")}</th>
+            <th>{i18n.t("email")}</th>
+            <th>{i18n.t("phone")}</th>
+            <th>{i18n.t("bookings")}</th>
+            <th>{i18n.t("total-spend")}</th>
+            <th>{i18n.t("dietary-preferences")}</th>
+            <th>{i18n.t("marketing")}</th>
+            <th>{i18n.t("possible-duplicates")}</th>
+          </tr>
+        </thead>
+        <tbody>
+          <For
+            each=move || contacts.clone()
+            key=|s| s.contact.id.clone()
+            children=move |s| {
+                let contact = store_value(s.contact.clone());
+                let toggle_consent = move |_| {
+                    let mut c = contact();
+                    c.marketing_consent = !c.marketing_consent;
+                    save.dispatch(c);
+                };
+                let duplicates = s
+                    .possible_duplicates
+                    .iter()
+                    .filter_map(|id| by_id.with_value(|m| m.get(id).cloned()))
+                    .map(|other| {
+                        let ids = (s.contact.id.clone(), other.id.clone());
+                        let label = i18n.t_with(
+                            "merge-contact",
+                            &[("name", other.name.clone().into()), ("email", other.email.clone().into())],
+                        );
+                        view! {
+                          <button class="button is-small" on:click=move |_| merge.dispatch(ids.clone())>
+                            {label}
+                          </button>
+                        }
+                    })
+                    .collect_view();
+                view! {
+                  <tr>
+                    <td>{s.contact.name}</td>
+                    <td>{s.contact.email}</td>
+                    <td>{s.contact.phone_no}</td>
+                    <td>
+                      <details>
+                        <summary>{s.bookings.len()}</summary>
+                        <ul>
+                          {s
+                              .bookings
+                              .iter()
+                              .map(|b| {
+                                  view! {
+                                    <li>
+                                      {i18n.t_with(
+                                          "contact-booking",
+                                          &[
+                                              ("event", b.event_id.clone().into()),
+                                              ("tickets", b.tickets.len().into()),
+                                              ("paid", i18n.money(&b.paid()).into()),
+                                          ],
+                                      )}
+                                    </li>
+                                  }
+                              })
+                              .collect_view()}
+                        </ul>
+                      </details>
+                    </td>
+                    <td>{i18n.totals(&s.total_spend)}</td>
+                    <td>{dietary_summary(&s.contact.dietary, &i18n)}</td>
+                    <td>
+                      <input type="checkbox" prop:checked=s.contact.marketing_consent on:change=toggle_consent/>
+                    </td>
+                    <td>{duplicates}</td>
+                  </tr>
+                }
+            }
+          />
+
+        </tbody>
+      </table>
+    }
+}
+
+fn dietary_summary(dietary: &DietaryPreferences, i18n: &I18n) -> String {
+    let mut parts = vec![];
+    if dietary.vegetarian {
+        parts.push(i18n.t("vegetarian"));
+    }
+    if dietary.gluten_free {
+        parts.push(i18n.t("gluten-free"));
+    }
+    if !dietary.requirements.is_empty() {
+        parts.push(dietary.requirements.clone());
+    }
+    parts.join(", ")
+}
@@ -0,0 +1,197 @@
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+use crate::components::controls::*;
+use crate::components::*;
+use crate::error::{describe, needs_sign_in};
+use crate::events::find_event;
+use crate::i18n::use_i18n;
+use crate::model::*;
+use crate::money::Money;
+use crate::server_fns::{delete_discount_code, list_discount_codes, save_discount_code};
+use leptos::*;
+use leptos_icons::FaIcon::*;
+
+#[component]
+pub fn Discounts() -> impl IntoView {
+    let i18n = use_i18n();
+    let save = create_action(|discount: &DiscountCode| {
+        let discount = discount.clone();
+        async move { save_discount_code(discount).await }
+    });
+    let delete = create_action(|code: &String| {
+        let code = code.clone();
+        async move { delete_discount_code(code).await }
+    });
+
+    let signed_in = create_trigger();
+
+    let codes = create_resource(
+        move || {
+            signed_in.track();
+            (save.version().get(), delete.version().get())
+        },
+        |_| async move { list_discount_codes().await },
+    );
+
+    let save_error = move || match save.value().get() {
+        Some(Err(e)) => Some(view! { <p class="help is-danger">{describe(&e)}</p> }),
+        _ => None,
+    };
+
+    view! {
+      <section class="section">
+        <div class="container">
+          <h1 class="title">{i18n.t("discount-codes")}</h1>
+          <Suspense fallback=move || {
+              view! { <p>{i18n.t("loading")}</p> }
+          }>
+
+            {move || match codes.get() {
+                None => view! { <p>{i18n.t("loading")}</p> }.into_view(),
+                Some(Err(e)) if needs_sign_in(&e) => {
+                    view! { <AdminSignIn signed_in=move || signed_in.notify()/> }.into_view()
+                }
+                Some(Err(e)) => {
+                    view! { <p>{i18n.t_with("discount-codes-failed", &[("error", describe(&e).into())])}</p> }
+                        .into_view()
+                }
+                Some(Ok(cs)) => view! { <DiscountsTable codes=cs delete=delete/> }.into_view(),
+            }}
+
+          </Suspense>
+
+          <div class="box">
+            <h2 class="subtitle">{i18n.t("add-a-code")}</h2>
+            <NewDiscount on_save=move |d| save.dispatch(d)/>
+            {save_error}
+          </div>
+        </div>
+      </section>
+    }
+}
+
+#[component]
+fn DiscountsTable(codes: Vec<DiscountCode>, delete: Action<String, Result<(), ServerFnError>>) -> impl IntoView {
+    let i18n = use_i18n();
+    view! {
+      <table class="table">
+        <thead>
+          <tr>
+            <th>{i18n.t("code")}</th>
+            <th>{i18n.t("event")}</th>
+            <th>{i18n.t("discount")}</th>
+            <th>{i18n.t("uses")}</th>
+            <th>{i18n.t("expires")}</th>
+            <th></th>
+          </tr>
+        </thead>
+        <tbody>
+          <For
+            each=move || codes.clone()
+            key=|d| d.code.clone()
+            children=move |d| {
+                let code = d.code.clone();
+                view! {
+                  <tr>
+                    <td>{d.code.clone()}</td>
+                    <td>{d.event_id.clone()}</td>
+                    <td>{i18n.discount(&d)}</td>
+                    <td>
+                      {match d.max_uses {
+                          Some(max) => format!("{} / {}", d.uses, max),
+                          None => d.uses.to_string(),
+                      }}
+                    </td>
+                    <td>{d.expires_at.map(|at| i18n.date(at)).unwrap_or_default()}</td>
+                    <td>
+                      <IconButton
+                        icon=FaTrashSolid
+                        size=Size::Small
+                        label=i18n.t_with("delete-code", &[("code", d.code.clone().into())])
+                        on_click=move || delete.dispatch(code.clone())
+                      />
+                    </td>
+                  </tr>
+                }
+            }
+          />
+
+        </tbody>
+      </table>
+    }
+}
+
+#[component]
+fn NewDiscount<F>(on_save: F) -> impl IntoView
+where
+    F: Fn(DiscountCode) + 'static,
+{
+    let i18n = use_i18n();
+    let (code, set_code) = create_signal(String::new());
+    let (event_id, set_event_id) = create_signal(String::new());
+    let (percentage, set_percentage) = create_signal(true);
+    let (amount, set_amount) = create_signal(String::new());
+    let (max_uses, set_max_uses) = create_signal(String::new());
+    let (expires, set_expires) = create_signal(String::new());
+
+    let discount = move || {
+        let amount = Decimal::from_str(&amount()).ok()?;
+        // A fixed discount comes off in the currency of the event it's for.
+        let currency = find_event(&event_id()).map(|e| e.currency).unwrap_or_default();
+        Some(DiscountCode {
+            code: code(),
+            event_id: event_id(),
+            kind: if percentage() {
+                DiscountKind::Percentage(amount)
+            } else {
+                DiscountKind::Fixed(Money::new(amount, currency))
+            },
+            max_uses: max_uses().parse().ok(),
+            uses: 0,
+            held: 0,
+            expires_at: NaiveDate::parse_from_str(&expires(), "%Y-%m-%d")
+                .ok()
+                .map(|d| Utc.from_utc_datetime(&d.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap()))),
+        })
+    };
+
+    view! {
+      <Field label=move || i18n.t("code")>
+        <Text placeholder=i18n.t("code-example") get=code set=move |new| set_code(new)/>
+        <Text placeholder=i18n.t("event-id") get=event_id set=move |new| set_event_id(new)/>
+      </Field>
+      <Field label=move || i18n.t("discount")>
+        <div class="control">
+          <div class="select">
+            <select on:change=move |ev| set_percentage(event_target_value(&ev) == "percentage")>
+              <option value="percentage">{i18n.t("percentage")}</option>
+              <option value="fixed">{i18n.t("fixed-amount")}</option>
+            </select>
+          </div>
+        </div>
+        <Text placeholder=i18n.t("amount") get=amount set=move |new| set_amount(new)/>
+      </Field>
+      <Field label=move || i18n.t("limits")>
+        <Text placeholder=i18n.t("max-uses-placeholder") get=max_uses set=move |new| set_max_uses(new)/>
+        <div class="control">
+          <input class="input" type="date" prop:value=expires on:change=move |ev| set_expires(event_target_value(&ev))/>
+        </div>
+      </Field>
+      <Field>
+        <IconButton
+          icon=FaPlusSolid
+          color=Color::Primary
+          disabled=Signal::derive(move || discount().is_none())
+          on_click=move || {
+              if let Some(d) = discount() {
+                  on_save(d);
+              }
+          }
+        >
+          {i18n.t("add-code")}
+        </IconButton>
+      </Field>
+    }
+}
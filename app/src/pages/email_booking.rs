@@ -1,7 +1,8 @@
+use crate::i18n::use_i18n;
 use leptos::*;
 
 #[component]
 pub fn EmailBooking() -> impl IntoView {
-    view! { <h1>Sending an email to sally...</h1> }
+    view! { <h1>{use_i18n().t("sending-email")}</h1> }
 }
 
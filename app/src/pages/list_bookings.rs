@@ -1,8 +1,11 @@
-use rust_decimal::Decimal;
 use tracing::*;
 use uuid::Uuid;
 
+use crate::components::AdminSignIn;
+use crate::error::{describe, needs_sign_in};
+use crate::i18n::use_i18n;
 use crate::model::*;
+use crate::money::{Money, Totals};
 use crate::server_fns::list_bookings;
 use leptos::*;
 
@@ -10,23 +13,31 @@ use leptos::*;
 enum Tabs {
     Bookings,
     Tickets,
+    AddOns,
 }
 
 #[component]
 pub fn ListBookings() -> impl IntoView {
-    let bookings = create_resource(|| (), |_| async move { list_bookings().await });
+    let i18n = use_i18n();
+    let signed_in = create_trigger();
+    let bookings = create_resource(move || signed_in.track(), |_| async move { list_bookings().await });
     let (active_tab, set_active_tab) = create_signal(Tabs::Bookings);
 
     view! {
       <section class="section">
         <div class="container">
           <Suspense fallback=move || {
-              view! { <p>"Loading..."</p> }
+              view! { <p>{i18n.t("loading")}</p> }
           }>
 
             {move || match bookings.get() {
-                None => view! { <p>"Loading..."</p> }.into_view(),
-                Some(Err(e)) => view! { <p>"Error loading bookings: " {e.to_string()}</p> }.into_view(),
+                None => view! { <p>{i18n.t("loading")}</p> }.into_view(),
+                Some(Err(e)) if needs_sign_in(&e) => {
+                    view! { <AdminSignIn signed_in=move || signed_in.notify()/> }.into_view()
+                }
+                Some(Err(e)) => {
+                    view! { <p>{i18n.t_with("bookings-failed", &[("error", describe(&e).into())])}</p> }.into_view()
+                }
                 Some(Ok(bs)) => {
                     view! {
                       <div class="tabs  is-medium is-boxed">
@@ -35,13 +46,19 @@ pub fn ListBookings() -> impl IntoView {
                             class:is-active=move || { active_tab.get() == Tabs::Bookings }
                             on:click=move |_| set_active_tab(Tabs::Bookings)
                           >
-                            <a>Bookings</a>
+                            <a>{i18n.t("bookings")}</a>
                           </li>
                           <li
                             class:is-active=move || { active_tab.get() == Tabs::Tickets }
                             on:click=move |_| set_active_tab(Tabs::Tickets)
                           >
-                            <a>Tickets</a>
+                            <a>{i18n.t("tickets")}</a>
+                          </li>
+                          <li
+                            class:is-active=move || { active_tab.get() == Tabs::AddOns }
+                            on:click=move |_| set_active_tab(Tabs::AddOns)
+                          >
+                            <a>{i18n.t("add-ons")}</a>
                           </li>
                         </ul>
                       </div>
@@ -49,6 +66,7 @@ pub fn ListBookings() -> impl IntoView {
                       {match active_tab() {
                           Tabs::Bookings => view! { <BookingsTab bookings=bs/> },
                           Tabs::Tickets => view! { <TicketsTab bookings=bs/> },
+                          Tabs::AddOns => view! { <AddOnsTab bookings=bs/> },
                       }}
                     }
                         .into_view()
@@ -63,18 +81,22 @@ pub fn ListBookings() -> impl IntoView {
 
 #[component]
 fn BookingsTab(bookings: Vec<Booking>) -> impl IntoView {
+    let i18n = use_i18n();
     let b2 = bookings.clone();
     let total_tickets = move || b2.clone().iter().map(|b| b.tickets.len()).sum::<usize>();
+    let total_donations = bookings.iter().map(|b| b.donation).collect::<Totals>();
     view! {
       <table class="table">
         <thead>
           <tr>
-            <th>Contact Name</th>
-            <th>Contact Email</th>
-            <th>Tickets</th>
-            <th>Price</th>
-            <th>Payment Type</th>
-            <th>Paid Amount</th>
+            <th>{i18n.t("contact-name")}</th>
+            <th>{i18n.t("contact-email")}</th>
+            <th>{i18n.t("tickets")}</th>
+            <th>{i18n.t("add-ons")}</th>
+            <th>{i18n.t("donation")}</th>
+            <th>{i18n.t("price")}</th>
+            <th>{i18n.t("payment-type")}</th>
+            <th>{i18n.t("paid-amount")}</th>
           </tr>
         </thead>
         <tbody>
@@ -82,28 +104,33 @@ fn BookingsTab(bookings: Vec<Booking>) -> impl IntoView {
             each=move || bookings.clone()
             key=|b| b.id.clone()
             children=move |b| {
-                let price = b.tickets.iter().fold(Decimal::new(0, 2), |a, t| { a + t.ticket_type.price });
-                let paid = match b.payment {
-                    BookingPayment::NotPaid => Decimal::new(0, 2),
-                    BookingPayment::Card(amt) | BookingPayment::Cash(amt) => amt,
-                };
+                let price = b.price();
+                let paid = b.paid();
+                let add_ons = b
+                    .add_ons
+                    .iter()
+                    .map(|o| format!("{} × {}", o.quantity, o.add_on.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
                 view! {
                   <tr>
                     <td>{b.contact.name}</td>
                     <td>{b.contact.email}</td>
                     <td>{b.tickets.len()}</td>
-                    <td>{format!("£{}", price)}</td>
+                    <td>{add_ons}</td>
+                    <td>{i18n.money(&b.donation)}</td>
+                    <td>{i18n.money(&price)}</td>
                     <td class:has-text-danger=b.payment
                         == BookingPayment::NotPaid>
                       {match b.payment {
-                          BookingPayment::NotPaid => "None".to_string(),
-                          BookingPayment::Card(_) => "Card".to_string(),
-                          BookingPayment::Cash(_) => "Cash".to_string(),
+                          BookingPayment::NotPaid => i18n.t("payment-none"),
+                          BookingPayment::Card(_) => i18n.t("payment-card"),
+                          BookingPayment::Cash(_) => i18n.t("payment-cash"),
                       }}
 
                     </td>
 
-                    <td class:has-text-success=paid == price>{format!("£{}", paid)}</td>
+                    <td class:has-text-success=paid == price>{i18n.money(&paid)}</td>
 
                   </tr>
                 }
@@ -115,9 +142,11 @@ fn BookingsTab(bookings: Vec<Booking>) -> impl IntoView {
           <tr>
             <th></th>
             <th>
-              <b>Total</b>
+              <b>{i18n.t("total")}</b>
             </th>
             <th>{total_tickets()}</th>
+            <th></th>
+            <th>{i18n.totals(&total_donations)}</th>
           </tr>
         </tfoot>
       </table>
@@ -131,6 +160,7 @@ fn TicketsTab(bookings: Vec<Booking>) -> impl IntoView {
         ticket: Ticket,
     }
 
+    let i18n = use_i18n();
     let bookings = store_value(bookings);
 
     let tickets = move || {
@@ -148,11 +178,12 @@ fn TicketsTab(bookings: Vec<Booking>) -> impl IntoView {
       <table class="table">
         <thead>
           <tr>
-            <th>Contact Name</th>
-            <th>Ticket Type</th>
-            <th>Vegetarian</th>
-            <th>Gluten Free</th>
-            <th>Other Requirements</th>
+            <th>{i18n.t("contact-name")}</th>
+            <th>{i18n.t("ticket-type")}</th>
+            <th>{i18n.t("attendee")}</th>
+            <th>{i18n.t("vegetarian")}</th>
+            <th>{i18n.t("gluten-free")}</th>
+            <th>{i18n.t("other-requirements")}</th>
           </tr>
         </thead>
         <tbody>
@@ -164,8 +195,9 @@ fn TicketsTab(bookings: Vec<Booking>) -> impl IntoView {
                   <tr>
                     <td>{b.contact.name}</td>
                     <td>{t.ticket_type.name}</td>
-                    <td>{if t.vegetarian { "yes" } else { "" }}</td>
-                    <td>{if t.gluten_free { "yes" } else { "" }}</td>
+                    <td>{t.attendee_name}</td>
+                    <td>{if t.vegetarian { i18n.t("yes") } else { String::new() }}</td>
+                    <td>{if t.gluten_free { i18n.t("yes") } else { String::new() }}</td>
                     <td>{if t.dietary_requirements != "none" { t.dietary_requirements } else { "".to_string() }}</td>
                   </tr>
                 }
@@ -176,7 +208,7 @@ fn TicketsTab(bookings: Vec<Booking>) -> impl IntoView {
         <tfoot>
           <tr>
             <th>
-              <b>Totals</b>
+              <b>{i18n.t("totals")}</b>
             </th>
             <th>{total_tickets()}</th>
             <th>{total_veggie()}</th>
@@ -187,3 +219,47 @@ fn TicketsTab(bookings: Vec<Booking>) -> impl IntoView {
     }
 }
 
+
+#[component]
+fn AddOnsTab(bookings: Vec<Booking>) -> impl IntoView {
+    let i18n = use_i18n();
+    let mut totals: Vec<(String, u32, Money)> = vec![];
+    for o in bookings.iter().flat_map(|b| b.add_ons.iter()) {
+        match totals
+            .iter_mut()
+            .find(|(name, _, price)| *name == o.add_on.name && price.currency == o.add_on.price.currency)
+        {
+            Some((_, quantity, price)) => {
+                *quantity += o.quantity;
+                *price = *price + o.price();
+            }
+            None => totals.push((o.add_on.name.clone(), o.quantity, o.price())),
+        }
+    }
+
+    view! {
+      <table class="table">
+        <thead>
+          <tr>
+            <th>{i18n.t("add-on")}</th>
+            <th>{i18n.t("quantity")}</th>
+            <th>{i18n.t("total")}</th>
+          </tr>
+        </thead>
+        <tbody>
+          {totals
+              .into_iter()
+              .map(|(name, quantity, price)| {
+                  view! {
+                    <tr>
+                      <td>{name}</td>
+                      <td>{quantity}</td>
+                      <td>{i18n.money(&price)}</td>
+                    </tr>
+                  }
+              })
+              .collect_view()}
+        </tbody>
+      </table>
+    }
+}
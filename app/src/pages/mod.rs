@@ -1,7 +1,15 @@
+mod booking_confirmed;
+mod booking_summary;
+mod contacts;
+mod discounts;
 mod email_booking;
 mod list_bookings;
 mod new_booking;
 
+pub use booking_confirmed::*;
+pub use booking_summary::*;
+pub use contacts::*;
+pub use discounts::*;
 pub use email_booking::*;
 pub use list_bookings::*;
 pub use new_booking::*;
@@ -2,44 +2,47 @@ use log::*;
 
 use crate::components::controls::*;
 use crate::components::*;
+use crate::draft::*;
+use crate::error::{describe, ErrorReport, FieldErrors};
+use crate::events::current_event;
+use crate::i18n::use_i18n;
 use crate::model::*;
+use crate::money::Money;
+use crate::pages::BookingSummary;
 use crate::reactive_list::*;
 use crate::server_fns::{create_order, create_payment_link};
 
+use chrono::Utc;
 use leptos::*;
 use leptos_icons::FaIcon::*;
-use rust_decimal_macros::dec;
 use uuid::Uuid;
 use validator::Validate;
 
-fn test_event() -> Event {
-    let ticket_types: [TicketType; 1] = [TicketType {
-        name: "Adult".into(),
-        price: dec!(15.00),
-        square_item_id: "VF54IAUH3FRNQMNE7T43ZXUB".into(),
-        square_catalog_version: 1700477397626,
-    }];
-
-    Event {
-        id: "xmas2023".into(),
-        name: "Little Stukeley Christmas Dinner".into(),
-        tagline: "Get your tickets for the final village event of the year!".into(),
-        ticket_types: TicketTypes::new(ticket_types),
-    }
-}
-
 #[component]
 pub fn NewBooking(without_payment: bool) -> impl IntoView {
-    let event = store_value(test_event());
-    let default_ticket_type = event().ticket_types.standard().unwrap();
+    let i18n = use_i18n();
+    let event = store_value(current_event());
+    let ticket_types = store_value(event().ticket_types.on_sale(Utc::now()));
+    let Some(default_ticket_type) = ticket_types().standard() else {
+        return view! {
+          <section class="section">
+            <div class="container">
+              <h1 class="title">{event().name}</h1>
+              <p class="subtitle">{i18n.t("tickets-not-on-sale")}</p>
+            </div>
+          </section>
+        }
+            .into_view();
+    };
 
-    let raw_booking = BookingContact::new("", "", event().id);
-    let first_ticket = Ticket::new(raw_booking.id.clone(), default_ticket_type);
-    let mut raw_tickets = ReactiveList::<Ticket>::new();
-    raw_tickets.insert(Uuid::new_v4(), create_rw_signal(first_ticket));
+    let new_contact = move || BookingContact {
+        phone_region: event().phone_region,
+        ..BookingContact::new("", "", event().id)
+    };
+    let raw_booking = new_contact();
+    let raw_tickets = ticket_list([Ticket::new(raw_booking.id.clone(), default_ticket_type)]);
 
     let (booking_contact, set_booking_contact) = create_signal::<BookingContact>(raw_booking);
-    let ticket_types = store_value(event().ticket_types);
     provide_context(ticket_types);
 
     let name = Signal::derive(move || booking_contact().name);
@@ -51,9 +54,62 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
     let phone_no = Signal::derive(move || booking_contact().phone_no);
     let set_phone_no = move |new| set_booking_contact.update(|b| b.phone_no = new);
 
+    let phone_region = Signal::derive(move || booking_contact().phone_region);
+    let set_phone_region = move |new| set_booking_contact.update(|b| b.phone_region = new);
+
+    let marketing_consent = Signal::derive(move || booking_contact().marketing_consent);
+    let set_marketing_consent = move |new| set_booking_contact.update(|b| b.marketing_consent = new);
+
     let (tickets, set_tickets) = create_signal::<ReactiveList<Ticket>>(raw_tickets);
+    let (discount, set_discount) = create_signal::<Option<DiscountCode>>(None);
+    let (add_ons, set_add_ons) = create_signal::<Vec<AddOnOrder>>(vec![]);
+    let (donation, set_donation) = create_signal(Money::zero(event().currency));
+    let (draft_id, set_draft_id) = create_signal(Uuid::new_v4().to_string());
+
+    let prefill = move |previous: PreviousBooking| {
+        set_booking_contact.update(|b| {
+            b.name = previous.name;
+            b.email = previous.email;
+            b.phone_no = previous.phone_no;
+            b.marketing_consent = previous.marketing_consent;
+        });
+        tickets.with_untracked(|ts| {
+            if let Some((_, first)) = ts.first() {
+                first.update(|t| {
+                    t.vegetarian = previous.dietary.vegetarian;
+                    t.gluten_free = previous.dietary.gluten_free;
+                    t.dietary_requirements = previous.dietary.requirements;
+                });
+            }
+        });
+    };
 
     let (error_seen, set_error_seen) = create_signal::<usize>(0);
+    let (field_errors, set_field_errors) = create_signal(FieldErrors::new());
+    let field_error = move |keys: Vec<String>| {
+        Signal::derive(move || {
+            let messages = field_errors.with(|errors| {
+                keys.iter().filter_map(|k| errors.get(k).cloned()).collect::<Vec<_>>()
+            });
+            (!messages.is_empty()).then(|| messages.join(". "))
+        })
+    };
+
+    // Tickets in a bundle are only bought together, so they're removed together too.
+    let remove_ticket = move |uid: Uuid| {
+        let bundle_id = tickets.with_untracked(|ts| {
+            ts.get(&uid)
+                .and_then(|t| t.get_untracked().bundle.map(|b| b.bundle_id))
+        });
+        match bundle_id {
+            None => set_tickets.tracked_remove(uid),
+            Some(id) => set_tickets.update(|ts| {
+                ts.retain(|_, t| t.get_untracked().bundle.map_or(true, |b| b.bundle_id != id))
+            }),
+        }
+    };
+
+    let ticket_label = move |i: usize| i18n.t_with("ticket-label", &[("number", (i + 1).into())]);
 
     let badgers = move || {
         tickets.with(|gl| {
@@ -63,19 +119,29 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
                 .map(|(i, (&uid, &gv))| {
                     if i == 0 {
                         view! {
-                          <Field label=move || format!("Ticket {}", { i + 1 })>
+                          <Field
+                            label=move || ticket_label(i)
+                            error=field_error(vec!["tickets".to_string(), "tickets.0".to_string()])
+                          >
                             <TicketControl ticket=gv/>
                           </Field>
                         }
                     } else {
                         view! {
-                          <Field label=move || {
-                              view! {
-                                {format!("Ticket {}", { i + 1 })}
-                                <br/>
-                                <IconButton on_click=move || set_tickets.tracked_remove(uid) icon=FaTrashSolid/>
-                              }
-                          }>
+                          <Field
+                            label=move || {
+                                view! {
+                                  {ticket_label(i)}
+                                  <br/>
+                                  <IconButton
+                                    on_click=move || remove_ticket(uid)
+                                    icon=FaTrashSolid
+                                    label=i18n.t_with("remove-ticket", &[("number", (i + 1).into())])
+                                  />
+                                }
+                            }
+                            error=field_error(vec![format!("tickets.{}", i)])
+                          >
                             <TicketControl ticket=gv/>
                           </Field>
                         }
@@ -92,6 +158,77 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
         ))
     };
 
+    let bundle_buttons = move || {
+        ticket_types()
+            .bundles()
+            .into_iter()
+            .map(|tt| {
+                let label = i18n.t_with(
+                    "add-bundle",
+                    &[("name", tt.name.clone().into()), ("price", i18n.money(&tt.price).into())],
+                );
+                let add_bundle = move || {
+                    for ticket in Ticket::bundle(booking_contact().id.clone(), tt.clone()) {
+                        set_tickets.tracked_push(ticket);
+                    }
+                };
+                view! {
+                  <p class="control">
+                    <IconButton icon=FaPlusSolid color=Color::Secondary on_click=add_bundle>
+                      {label}
+                    </IconButton>
+                  </p>
+                }
+            })
+            .collect_view()
+    };
+
+    let add_on_fields = move || {
+        event()
+            .add_ons
+            .into_iter()
+            .map(|add_on| {
+                let name = add_on.name.clone();
+                let quantity = {
+                    let name = name.clone();
+                    Signal::derive(move || {
+                        add_ons.with(|os| os.iter().find(|o| o.add_on.name == name).map_or(0, |o| o.quantity))
+                    })
+                };
+                let set_quantity = {
+                    let add_on = add_on.clone();
+                    move |quantity: u32| {
+                        set_add_ons.update(|os| {
+                            match os.iter_mut().find(|o| o.add_on.name == add_on.name) {
+                                Some(o) => o.quantity = quantity,
+                                None => os.push(AddOnOrder {
+                                    add_on: add_on.clone(),
+                                    quantity,
+                                }),
+                            }
+                            os.retain(|o| o.quantity > 0);
+                        })
+                    }
+                };
+                let error = field_error(vec![format!("add_ons.{}", name)]);
+                view! {
+                  <Field label=move || name.clone() error=error>
+                    <AddOnQuantity add_on=add_on get=quantity set=set_quantity/>
+                  </Field>
+                }
+            })
+            .collect_view()
+    };
+
+    let donation_field = move || match event().donations {
+        Donations::Fund(fund) => Some(view! {
+          <Field label=move || i18n.t("donation") error=field_error(vec!["donation".to_string()])>
+            <DonationAmount fund=fund get=donation set=move |d| set_donation(d)/>
+          </Field>
+        }),
+        _ => None,
+    };
+
     let build_booking = move || {
         let contact = booking_contact().to_owned();
         let tickets = tickets()
@@ -99,12 +236,50 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
             .map(|(_, t)| t().to_owned())
             .collect::<Vec<_>>();
         NewBooking {
+            draft_id: draft_id(),
             event_id: event().id,
             contact,
             tickets,
+            add_ons: add_ons(),
+            donation: donation(),
+            discount: discount(),
         }
     };
 
+    let booking = Signal::derive(build_booking);
+    let (confirming, set_confirming) = create_signal(false);
+
+    let start_again = move || {
+        let contact = new_contact();
+        let first_ticket = Ticket::new(contact.id.clone(), ticket_types().standard().unwrap());
+        set_booking_contact(contact);
+        set_tickets(ticket_list([first_ticket]));
+        set_add_ons(vec![]);
+        set_donation(Money::zero(event().currency));
+        set_draft_id(Uuid::new_v4().to_string());
+        set_discount(None);
+        clear_draft(&event().id);
+    };
+
+    // Restore any draft from a previous visit, then keep it up to date as the form changes.
+    create_effect(move |restored: Option<()>| {
+        if restored.is_none() {
+            if let Some(draft) = load_draft(&event().id) {
+                if !draft.draft_id.is_empty() {
+                    set_draft_id(draft.draft_id);
+                }
+                set_booking_contact(draft.contact);
+                set_add_ons(draft.add_ons);
+                set_donation(draft.donation);
+                set_discount(draft.discount);
+                if !draft.tickets.is_empty() {
+                    set_tickets(ticket_list(draft.tickets));
+                }
+            }
+        }
+        save_draft(&build_booking());
+    });
+
     let link_action = create_action(move |_: &()| {
         let new_booking = build_booking();
         async move { create_payment_link(new_booking).await }
@@ -118,65 +293,114 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
     let create_order_pending = create_order.pending();
     let create_order_value = create_order.value();
     let create_order_text = move || match create_order_value() {
-        Some(Ok(v)) => format!("Order Created: id: {} ", v),
-        Some(Err(e)) => format!("Error Creating Order: {}", e),
-        None => "Pending..".to_string(),
+        Some(Ok(v)) => i18n.t_with("order-created", &[("id", v.into())]),
+        Some(Err(e)) => i18n.t_with("order-failed", &[("error", describe(&e).into())]),
+        None => i18n.t("order-pending"),
     };
     let (create_error_seen, set_create_error_seen) = create_signal::<usize>(0);
 
-    let error_data = move || {
+    create_effect(move |_| {
+        if let Some(Ok(_)) = create_order_value() {
+            start_again();
+        }
+    });
+
+    let error_report = move || {
         link_action.value().with(|x| {
             if let Some(Err(err)) = x {
-                Some(err.to_string())
+                Some(ErrorReport::from(err))
             } else {
                 None
             }
         })
     };
 
+    create_effect(move |_| {
+        let errors = error_report().and_then(|r| r.error.field_errors().cloned());
+        set_field_errors(errors.unwrap_or_default());
+    });
+
     let _navigate_to_payment = create_effect(move |_| {
         link_action.value().with(|x| {
             if let Some(Ok(res)) = x {
+                clear_draft(&event().id);
                 let _ = window().location().set_href(res);
             }
         })
     });
 
-    let validation = move || booking_contact().validate();
-    let is_invalid = Signal::derive(move || validation().is_err());
+    let pays_online = event().payment == PaymentMethod::Square;
+
+    let is_invalid = Signal::derive(move || {
+        let booking = build_booking();
+        booking.tickets.is_empty() || booking.validate().is_err()
+    });
     let pending = link_action.pending();
 
     view! {
       <section class="section">
         <div class="container">
-          <h1 class="title">Little Stukeley Christmas Dinner</h1>
-          <p class="subtitle">Get your tickets for the final village event of the year!</p>
+          <h1 class="title">{event().name}</h1>
+          <p class="subtitle">{event().tagline}</p>
 
           <div class="box">
-            <Field label=|| "Booking Contact">
+            <Field>
+              <ReturningBooker on_found=prefill/>
+            </Field>
+            <Field
+              label=move || i18n.t("booking-contact")
+              error=field_error(vec!["contact.name".to_string(), "contact.email".to_string()])
+            >
               <Name get=name set=set_name/>
               <Email get=email set=set_email/>
             </Field>
+            <Field error=field_error(vec!["contact.phone_no".to_string()])>
+              <PhoneNumber get=phone_no set=set_phone_no region=phone_region set_region=set_phone_region/>
+            </Field>
             <Field>
-              <PhoneNumber get=phone_no set=set_phone_no/>
+              <Checkbox
+                label=i18n.t("marketing-consent")
+                get=marketing_consent
+                set=set_marketing_consent
+              />
             </Field>
             {badgers}
+            {add_on_fields}
+            {donation_field}
+
+            <Field label=move || i18n.t("discount")>
+              <DiscountCodeEntry event_id=event().id get=discount set=move |d| set_discount(d)/>
+            </Field>
+
+            <div class="block">
+              <OrderSummary booking=booking/>
+            </div>
 
             <div class="field is-grouped is-flex-wrap-wrap">
               <p class="control">
                 <IconButton icon=FaPlusSolid color=Color::Secondary on_click=add_ticket>
-                  "Add Another Ticket"
+                  {i18n.t("add-ticket")}
                 </IconButton>
               </p>
+              {bundle_buttons}
 
               <p class="control">
                 <IconButton
                   disabled=is_invalid
                   icon=FaBasketShoppingSolid
                   color=Color::Primary
-                  on_click=move || link_action.dispatch(())
+                  on_click=move || set_confirming(true)
                 >
-                  {move || { if pending() { "Generating Link..." } else { "Proceed to Payment" } }}
+                  {move || match (pending(), pays_online) {
+                      (true, _) => i18n.t("booking-in-progress"),
+                      (false, true) => i18n.t("proceed-to-payment"),
+                      (false, false) => i18n.t("book-now"),
+                  }}
+                </IconButton>
+              </p>
+              <p class="control">
+                <IconButton icon=FaArrowRotateLeftSolid on_click=start_again>
+                  {i18n.t("start-again")}
                 </IconButton>
               </p>
               <Show when=move || without_payment>
@@ -187,7 +411,9 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
                     color=Color::Primary
                     on_click=move || create_order.dispatch(())
                   >
-                    {move || { if pending() { "Creating Order..." } else { "Create Order without Paying" } }}
+                    {move || {
+                        if pending() { i18n.t("creating-order") } else { i18n.t("create-order-without-paying") }
+                    }}
                   </IconButton>
                 </p>
               </Show>
@@ -196,33 +422,69 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
         </div>
 
         <Modal
-          active=move || error_data().is_some() && link_action.version()() != error_seen()
+          active=confirming
+          close_requested=move || set_confirming(false)
+          title=i18n.t("booking-summary")
+          footer=move || {
+              view! {
+                <IconButton
+                  icon=FaBasketShoppingSolid
+                  color=Color::Primary
+                  on_click=move || {
+                      set_confirming(false);
+                      link_action.dispatch(());
+                  }
+                >
+                  {if pays_online { i18n.t("confirm-and-pay") } else { i18n.t("confirm-booking") }}
+                </IconButton>
+                <button class="button" on:click=move |_| set_confirming(false)>
+                  {i18n.t("back")}
+                </button>
+              }
+          }
+        >
+
+          <BookingSummary booking=booking/>
+        </Modal>
+
+        <Modal
+          active=move || {
+              error_report().is_some_and(|r| r.error.field_errors().is_none())
+                  && link_action.version()() != error_seen()
+          }
           close_requested=move || set_error_seen(link_action.version()())
-          title="Oh dear"
+          title=i18n.t("booking-failed-title")
           footer=move || {
               view! {}
           }
         >
 
-          <div class="block">Something went wrong trying to generate a payment link for you to buy your tickets.</div>
-          <div class="block">
-            Terribly sorry about that. Could you please let me (Phil Scott) know and tell me what it says below and I will get it sorted
-          </div>
-          <div class="block">
-            <pre style="white-space: pre-wrap;">{error_data}</pre>
-          </div>
+          <div class="block">{i18n.t("booking-failed")}</div>
+          <div class="block">{move || error_report().map(|r| r.error.message())}</div>
+          {move || {
+              error_report()
+                  .filter(|r| !r.error.is_users_to_fix())
+                  .and_then(|r| r.correlation_id)
+                  .map(|id| {
+                      view! {
+                        <div class="block">
+                          {i18n.t("booking-failed-reference")} " " <strong>{id}</strong> "."
+                        </div>
+                      }
+                  })
+          }}
         </Modal>
 
         <Modal
           active=move || !create_order_pending() && create_order.version()() != create_error_seen()
           close_requested=move || set_create_error_seen(create_order.version()())
-          title="Create Order Results"
+          title=i18n.t("create-order-results")
           footer=move || {
               view! {}
           }
         >
 
-          <div class="block">Hi Sally</div>
+          <div class="block">{i18n.t("create-order-greeting")}</div>
           <div class="block">
             <pre style="white-space: pre-wrap;">{create_order_text}</pre>
           </div>
@@ -230,5 +492,12 @@ pub fn NewBooking(without_payment: bool) -> impl IntoView {
 
       </section>
     }
+        .into_view()
 }
 
+fn ticket_list<I: IntoIterator<Item = Ticket>>(tickets: I) -> ReactiveList<Ticket> {
+    tickets
+        .into_iter()
+        .map(|t| (Uuid::new_v4(), create_rw_signal(t)))
+        .collect()
+}
@@ -0,0 +1,62 @@
+//! The ways an event can take payment. Every booking is recorded as a Square order
+//! whichever provider is used; they differ in how, and whether, it's paid for online.
+
+mod on_the_door;
+pub mod square;
+
+pub use on_the_door::OnTheDoor;
+pub use square::SquareCheckout;
+
+use crate::error::ServiceError;
+use crate::model::*;
+use crate::money::Money;
+use async_trait::async_trait;
+
+/// Everything a provider needs to start paying for a booking.
+pub struct CheckoutRequest<'a> {
+    pub booking: &'a NewBooking,
+    pub event: &'a Event,
+    pub customer_id: String,
+    pub discount: Option<&'a DiscountCode>,
+    pub idempotency_key: String,
+}
+
+pub struct Checkout {
+    pub order_id: String,
+    /// Where to send the booker next.
+    pub url: String,
+    /// Whether the booker still has to pay online, so the checkout could be abandoned.
+    pub awaiting_payment: bool,
+    /// The payment link the booker was sent to, so it can be taken down if they don't pay.
+    pub link_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaymentState {
+    Unpaid,
+    Paid(Money),
+    Cancelled,
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Creates the order for a booking, returning where to send the booker to pay for it.
+    async fn create_checkout(&self, req: CheckoutRequest<'_>) -> Result<Checkout, ServiceError>;
+
+    async fn payment_state(&self, order_id: &str) -> Result<PaymentState, ServiceError>;
+
+    /// Refunds `amount` of an order. `refund_id` identifies this refund, so the caller
+    /// should make one up for each refund it means to give, keep it, and use it again
+    /// if it has to retry; two refunds with the same id are only given once.
+    async fn refund(&self, order_id: &str, refund_id: &str, amount: Money) -> Result<(), ServiceError>;
+
+    /// Checks a webhook notification sent to `url` really came from the provider.
+    fn verify_webhook(&self, url: &str, body: &[u8], signature: &str) -> bool;
+}
+
+pub fn provider_for(method: &PaymentMethod) -> Box<dyn PaymentProvider> {
+    match method {
+        PaymentMethod::Square => Box::new(SquareCheckout),
+        PaymentMethod::OnTheDoor => Box::new(OnTheDoor),
+    }
+}
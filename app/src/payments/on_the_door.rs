@@ -0,0 +1,29 @@
+use super::*;
+use crate::i18n::use_i18n;
+
+/// Books without taking payment online; we collect it on the door or send an invoice.
+pub struct OnTheDoor;
+
+#[async_trait]
+impl PaymentProvider for OnTheDoor {
+    async fn create_checkout(&self, req: CheckoutRequest<'_>) -> Result<Checkout, ServiceError> {
+        let order_id = square::create_order(&req).await?;
+        Ok(Checkout {
+            url: format!("/booked?order={}", order_id),
+            order_id,
+            awaiting_payment: false,
+            link_id: None,
+        })
+    }
+
+    /// Payments taken on the door are put through the Square till against the order.
+    async fn payment_state(&self, order_id: &str) -> Result<PaymentState, ServiceError> {
+        SquareCheckout.payment_state(order_id).await
+    }
+
+    async fn refund(&self, _order_id: &str, _refund_id: &str, _amount: Money) -> Result<(), ServiceError> {
+        Err(ServiceError::Validation(use_i18n().t("refund-by-hand")))
+    }
+
+    fn verify_webhook(&self, _url: &str, _body: &[u8], _signature: &str) -> bool { false }
+}
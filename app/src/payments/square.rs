@@ -0,0 +1,186 @@
+use super::*;
+use crate::i18n::use_i18n;
+use crate::server_fns::{build_order, complete_checkout};
+use crate::square_api;
+use crate::square_client::square;
+use axum::{body::Bytes, http::HeaderMap, http::StatusCode};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::*;
+
+/// Takes payment online through a Square payment link.
+pub struct SquareCheckout;
+
+#[async_trait]
+impl PaymentProvider for SquareCheckout {
+    async fn create_checkout(&self, req: CheckoutRequest<'_>) -> Result<Checkout, ServiceError> {
+        // Checked when the server started, by `config::check_events`.
+        let checkout = &req.event.checkout;
+
+        let phone_number = req
+            .booking
+            .contact
+            .phone_number()
+            .map_err(|_| ServiceError::Validation(use_i18n().t("phone-number-invalid")))?
+            .format()
+            .mode(phonenumber::Mode::E164)
+            .to_string();
+
+        let body = square_api::CreatePaymentLinkRequest {
+            idempotency_key: req.idempotency_key.clone(),
+            description: checkout.description.clone(),
+            order: build_order(req.booking, req.event, req.customer_id.clone(), req.discount)?,
+            checkout_options: Some(square_api::CheckoutOptions {
+                allow_tipping: req.event.donations == Donations::Tipping,
+                ask_for_shipping_address: checkout.ask_for_shipping_address,
+                enable_coupon: checkout.enable_coupon,
+                enable_loyalty: checkout.enable_loyalty,
+                redirect_url: checkout.redirect_url.clone(),
+                merchant_support_email: checkout.support_email.clone(),
+            }),
+            pre_populated_data: Some(square_api::PrePopulatedData {
+                buyer_address: None,
+                buyer_email: Some(req.booking.contact.email.clone()),
+                buyer_phone_number: Some(phone_number),
+            }),
+        };
+
+        let link = square()?.create_payment_link(&body).await?.payment_link;
+        Ok(Checkout {
+            order_id: link.order_id,
+            url: link.long_url,
+            awaiting_payment: true,
+            link_id: Some(link.id),
+        })
+    }
+
+    async fn payment_state(&self, order_id: &str) -> Result<PaymentState, ServiceError> {
+        let order = retrieve_order(order_id).await?;
+        let currency = order.total_money.as_ref().map(|m| m.currency).unwrap_or_default();
+        let paid = order
+            .tenders
+            .iter()
+            .filter_map(|t| t.amount_money.as_ref())
+            .fold(Money::zero(currency), |paid, m| paid + Money::from(m));
+
+        Ok(match order.state.as_str() {
+            "CANCELED" => PaymentState::Cancelled,
+            _ if order.tenders.is_empty() => PaymentState::Unpaid,
+            _ => PaymentState::Paid(paid),
+        })
+    }
+
+    async fn refund(&self, order_id: &str, refund_id: &str, amount: Money) -> Result<(), ServiceError> {
+        let order = retrieve_order(order_id).await?;
+        let payment_id = order
+            .tenders
+            .iter()
+            .find_map(|t| t.payment_id.clone())
+            .ok_or_else(|| ServiceError::payment(format!("order {} has no card payment to refund", order_id)))?;
+
+        info!(order_id = order_id, refund_id = refund_id, amount = %amount, "refunding");
+        let body = square_api::RefundPaymentRequest {
+            idempotency_key: format!("refund-{}", refund_id),
+            payment_id,
+            amount_money: amount.into(),
+        };
+        square()?.refund_payment(&body).await?;
+        Ok(())
+    }
+
+    fn verify_webhook(&self, url: &str, body: &[u8], signature: &str) -> bool {
+        let square = match square() {
+            Ok(square) => square,
+            Err(e) => {
+                warn!(error = %e, "can't check webhook signature");
+                return false;
+            }
+        };
+        let Some(key) = square.config().webhook_signature_key.as_ref() else {
+            warn!("no webhook signature key is configured, rejecting webhook");
+            return false;
+        };
+        let Ok(signature) = base64::engine::general_purpose::STANDARD.decode(signature) else {
+            return false;
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+        mac.update(url.as_bytes());
+        mac.update(body);
+        mac.verify_slice(&signature).is_ok()
+    }
+}
+
+/// Creates the Square order for a booking without asking for payment.
+pub(crate) async fn create_order(req: &CheckoutRequest<'_>) -> Result<String, ServiceError> {
+    let body = square_api::CreateOrderRequest {
+        idempotency_key: req.idempotency_key.clone(),
+        order: build_order(req.booking, req.event, req.customer_id.clone(), req.discount)?,
+    };
+
+    let order = square()?.create_order(&body).await?;
+    info!("order created: {:?}", order);
+    Ok(order.id)
+}
+
+pub(crate) async fn cancel_order(order_id: &str) -> Result<(), ServiceError> {
+    let order = retrieve_order(order_id).await?;
+    let body = square_api::UpdateOrderRequest {
+        idempotency_key: format!("cancel-{}", order_id),
+        order: square_api::OrderUpdate {
+            location_id: order.location_id,
+            version: order.version,
+            state: "CANCELED".to_string(),
+        },
+    };
+    square()?.update_order(order_id, &body).await?;
+    Ok(())
+}
+
+/// Takes down a payment link, which cancels its order too.
+pub(crate) async fn delete_payment_link(link_id: &str) -> Result<(), ServiceError> {
+    let deleted = square()?.delete_payment_link(link_id).await?;
+    debug!(link_id = link_id, order_id = ?deleted.cancelled_order_id, "payment link deleted");
+    Ok(())
+}
+
+async fn retrieve_order(order_id: &str) -> Result<square_api::Order, ServiceError> {
+    Ok(square()?.retrieve_order(order_id).await?)
+}
+
+/// Receives Square's webhook notifications, so a booking is confirmed as soon as
+/// it's paid rather than waiting to be checked on by the cleanup job.
+pub async fn square_webhook(headers: HeaderMap, body: Bytes) -> StatusCode {
+    let square = match square() {
+        Ok(square) => square,
+        Err(e) => {
+            error!(error = %e, "can't take webhooks");
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+    };
+    let url = &square.config().webhook_url;
+    let signature = headers
+        .get("x-square-hmacsha256-signature")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !SquareCheckout.verify_webhook(url, &body, signature) {
+        warn!("rejecting webhook with a bad signature");
+        return StatusCode::FORBIDDEN;
+    }
+
+    let event = match serde_json::from_slice::<square_api::WebhookEvent>(&body) {
+        Ok(event) => event,
+        Err(e) => {
+            warn!(error = %e, "unreadable webhook");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    debug!(event_type = %event.event_type, "webhook received");
+
+    if let Some(payment) = event.data.object.payment {
+        if payment.status == "COMPLETED" {
+            complete_checkout(&payment.order_id);
+        }
+    }
+    StatusCode::OK
+}
@@ -0,0 +1,92 @@
+use leptos::*;
+
+cfg_if::cfg_if! {
+if #[cfg(feature = "ssr")] {
+    use crate::config::app_config;
+    use crate::error::ServiceError;
+    use crate::i18n::use_i18n;
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use tracing::*;
+}}
+
+/// The cookie that marks a browser as signed in to the admin pages.
+#[cfg(feature = "ssr")]
+const SESSION_COOKIE: &str = "happenings_admin";
+#[cfg(feature = "ssr")]
+const SESSION_DAYS: u32 = 30;
+
+/// What a signed in browser's cookie has to match. It's made from the password,
+/// so the password itself never sits in a cookie, and changing it signs everyone out.
+#[cfg(feature = "ssr")]
+fn session_mac(password: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(password.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(b"happenings admin session");
+    mac
+}
+
+#[cfg(feature = "ssr")]
+fn admin_password() -> Result<String, ServiceError> {
+    app_config()?.admin.password.ok_or_else(|| {
+        warn!("no admin password is configured, refusing admin request");
+        ServiceError::Forbidden
+    })
+}
+
+/// Refuses the request unless it comes from a browser signed in to the admin pages.
+#[cfg(feature = "ssr")]
+pub(crate) fn require_admin() -> Result<(), ServiceError> {
+    let password = admin_password()?;
+    let session = use_context::<http::request::Parts>()
+        .and_then(|parts| session_cookie(&parts.headers))
+        .and_then(|value| {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(value)
+                .ok()
+        });
+
+    match session {
+        Some(session) if session_mac(&password).verify_slice(&session).is_ok() => Ok(()),
+        _ => Err(ServiceError::Forbidden),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn session_cookie(headers: &http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(';'))
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Signs this browser in to the admin pages, if `password` is the admin password.
+#[server(SignIn, "/api")]
+pub async fn sign_in(password: String) -> Result<(), ServerFnError> {
+    let expected = admin_password()?;
+    let given = session_mac(&password).finalize().into_bytes();
+    if session_mac(&expected).verify_slice(&given).is_err() {
+        info!("admin sign in refused");
+        return Err(ServiceError::Validation(use_i18n().t("admin-password-wrong")).into());
+    }
+
+    let session = session_mac(&expected).finalize().into_bytes();
+    let session = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(session);
+    let cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Strict",
+        SESSION_COOKIE,
+        session,
+        SESSION_DAYS * 24 * 60 * 60
+    );
+    let response = use_context::<leptos_axum::ResponseOptions>()
+        .ok_or_else(|| ServiceError::unexpected("no response to set the session cookie on"))?;
+    let cookie = http::HeaderValue::from_str(&cookie).map_err(ServiceError::unexpected)?;
+    response.insert_header(http::header::SET_COOKIE, cookie);
+    info!("admin signed in");
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use super::contacts::record_contact;
+use super::discounts::{redeem_discount, release_discount};
+use crate::error::ServiceError;
+use crate::model::*;
+use crate::payments::{square, Checkout, PaymentProvider, PaymentState, SquareCheckout};
+use crate::store::JsonStore;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use tracing::*;
+
+/// How long a payment link is offered for before we give up on it and cancel its order.
+const CHECKOUT_TTL_MINUTES: i64 = 30;
+
+/// A payment link we've handed out that hasn't been paid yet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct PendingCheckout {
+    pub order_id: String,
+    pub url: String,
+    /// The payment link itself, which is deleted if it isn't paid in time.
+    #[serde(default)]
+    pub link_id: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    /// What to record once it's paid.
+    #[serde(default)]
+    pub on_payment: Option<ConfirmOnPayment>,
+}
+
+/// A booking that only counts once it's been paid for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ConfirmOnPayment {
+    pub customer_id: String,
+    pub booking: NewBooking,
+    /// The discount code whose use is held for the booking.
+    #[serde(default)]
+    pub discount: Option<String>,
+}
+
+impl ConfirmOnPayment {
+    /// Gives back what was held for a booking that won't be made after all.
+    pub(crate) fn abandon(&self) {
+        if let Some(code) = &self.discount {
+            release_discount(code);
+        }
+    }
+}
+
+fn pending_store() -> &'static JsonStore<PendingCheckout> {
+    static STORE: OnceLock<JsonStore<PendingCheckout>> = OnceLock::new();
+    STORE.get_or_init(|| JsonStore::open("pending_checkouts"))
+}
+
+/// When each order's booking was confirmed, by order id.
+fn confirmed_store() -> &'static JsonStore<DateTime<Utc>> {
+    static STORE: OnceLock<JsonStore<DateTime<Utc>>> = OnceLock::new();
+    STORE.get_or_init(|| JsonStore::open("confirmed_orders"))
+}
+
+/// How many of each draft's checkouts have expired unpaid, by the draft's key.
+fn attempts_store() -> &'static JsonStore<u32> {
+    static STORE: OnceLock<JsonStore<u32>> = OnceLock::new();
+    STORE.get_or_init(|| JsonStore::open("checkout_attempts"))
+}
+
+/// The Square idempotency key for a booking: the same every time the same draft is
+/// submitted unchanged, so double clicks and retries don't create duplicate orders.
+/// The hash has to be one that's the same across builds and restarts.
+pub(crate) fn idempotency_key(booking: &NewBooking) -> String {
+    let digest = Sha256::digest(serde_json::to_vec(booking).unwrap_or_default());
+    let hash = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest);
+    match booking.draft_id.as_str() {
+        "" => hash,
+        draft_id => format!("{}-{}", draft_id, hash),
+    }
+}
+
+/// The key to send Square for a new checkout of the draft with `key`. It changes each
+/// time one of the draft's checkouts expires, so Square makes a new payment link rather
+/// than handing back the one that's been taken down.
+pub(crate) fn attempt_key(key: &str) -> String {
+    match attempts_store().get(key).unwrap_or_default() {
+        0 => key.to_string(),
+        attempt => format!("{}-{}", key, attempt),
+    }
+}
+
+/// The link previously made for `key`, if it's still open for payment, or has been
+/// paid. One that's expired unpaid is taken down first, so a new one can be made.
+pub(crate) async fn reusable_checkout(key: &str) -> Option<PendingCheckout> {
+    let checkout = pending_store().get(key)?;
+    if Utc::now() < checkout.expires_at {
+        return Some(checkout);
+    }
+    match expire_checkout(key, &checkout).await {
+        Ok(PaymentState::Paid(_)) => Some(checkout),
+        Ok(_) => None,
+        Err(e) => {
+            warn!(order_id = %checkout.order_id, error = %e, "failed to clean up checkout");
+            None
+        }
+    }
+}
+
+pub(crate) fn track_checkout(key: &str, made: &Checkout, on_payment: ConfirmOnPayment) {
+    let checkout = PendingCheckout {
+        order_id: made.order_id.clone(),
+        url: made.url.clone(),
+        link_id: made.link_id.clone(),
+        expires_at: Utc::now() + Duration::minutes(CHECKOUT_TTL_MINUTES),
+        on_payment: Some(on_payment),
+    };
+    // Only one checkout is tracked per key, so whatever the one it replaces held goes back.
+    if let Some(replaced) = pending_store().get(key).and_then(|c| c.on_payment) {
+        replaced.abandon();
+    }
+    if let Err(e) = pending_store().insert(key, checkout) {
+        warn!(key = key, error = %e, "failed to record pending checkout");
+    }
+}
+
+/// Records what a booking means for the booker, and counts its discount code as
+/// used, once it's definitely been made. Each order is only confirmed once: a draft
+/// submitted again gets the same order back from Square, and what was held for the
+/// repeat is given back instead.
+pub(crate) fn confirm_booking(order_id: &str, confirm: &ConfirmOnPayment) {
+    match confirmed_store().insert_new(order_id, Utc::now()) {
+        Ok(true) => {}
+        Ok(false) => {
+            debug!(order_id = order_id, "booking already confirmed");
+            confirm.abandon();
+            return;
+        }
+        Err(e) => warn!(order_id = order_id, error = %e, "failed to record confirmed order"),
+    }
+    record_contact(&confirm.customer_id, &confirm.booking);
+    if let Some(code) = &confirm.discount {
+        redeem_discount(code);
+    }
+}
+
+/// Confirms the booking behind an order once it's been paid, and stops tracking its checkout.
+/// Safe to call more than once for the same order, as Square may tell us more than once.
+pub(crate) fn complete_checkout(order_id: &str) {
+    for (key, checkout) in pending_store()
+        .entries()
+        .into_iter()
+        .filter(|(_, c)| c.order_id == order_id)
+    {
+        match pending_store().remove(&key) {
+            Ok(Some(_)) => {
+                if let Some(paid) = checkout.on_payment {
+                    confirm_booking(order_id, &paid);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!(key = %key, error = %e, "failed to forget pending checkout"),
+        }
+    }
+}
+
+/// Takes down expired payment links that were never paid, which cancels their
+/// orders, so abandoned checkouts don't show up as bookings, and confirms any that
+/// were paid without Square's webhook reaching us.
+pub async fn cancel_abandoned_checkouts() { cancel_checkouts_expired_by(Utc::now()).await }
+
+/// Settles every checkout that will have expired by `time`, as though it were now.
+pub async fn cancel_checkouts_expired_by(time: DateTime<Utc>) {
+    let expired = pending_store()
+        .entries()
+        .into_iter()
+        .filter(|(_, c)| c.expires_at <= time);
+
+    for (key, checkout) in expired {
+        if let Err(e) = expire_checkout(&key, &checkout).await {
+            warn!(order_id = %checkout.order_id, error = %e, "failed to clean up checkout");
+        }
+    }
+}
+
+/// Settles an expired checkout: confirms it if it was paid after all, otherwise takes
+/// it down, gives back what it held and moves its draft on to a new key.
+async fn expire_checkout(key: &str, checkout: &PendingCheckout) -> Result<PaymentState, ServiceError> {
+    let state = cancel_if_unpaid(checkout).await?;
+    if let PaymentState::Paid(_) = state {
+        complete_checkout(&checkout.order_id);
+        return Ok(state);
+    }
+
+    match pending_store().remove(key) {
+        Ok(Some(removed)) => {
+            if let Some(abandoned) = removed.on_payment {
+                abandoned.abandon();
+            }
+            next_attempt(key);
+        }
+        Ok(None) => {}
+        Err(e) => warn!(key = key, error = %e, "failed to forget pending checkout"),
+    }
+    Ok(state)
+}
+
+/// Takes a checkout down if it hasn't been paid, returning the state it was found in.
+async fn cancel_if_unpaid(checkout: &PendingCheckout) -> Result<PaymentState, ServiceError> {
+    let order_id = checkout.order_id.as_str();
+    let state = SquareCheckout.payment_state(order_id).await?;
+    match (&state, &checkout.link_id) {
+        (PaymentState::Unpaid, Some(link_id)) => {
+            info!(order_id = order_id, "deleting abandoned payment link");
+            square::delete_payment_link(link_id).await?;
+        }
+        (PaymentState::Unpaid, None) => {
+            info!(order_id = order_id, "cancelling abandoned checkout");
+            square::cancel_order(order_id).await?;
+        }
+        _ => debug!(order_id = order_id, state = ?state, "checkout completed, nothing to cancel"),
+    }
+    Ok(state)
+}
+
+fn next_attempt(key: &str) {
+    let store = attempts_store();
+    let counted = store
+        .update(key, |attempt| *attempt += 1)
+        .and_then(|updated| match updated {
+            Some(_) => Ok(()),
+            None => store.insert(key, 1),
+        });
+    if let Err(e) = counted {
+        warn!(key = key, error = %e, "failed to count checkout attempt");
+    }
+}
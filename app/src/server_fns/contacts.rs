@@ -0,0 +1,201 @@
+use crate::model::*;
+use leptos::*;
+
+cfg_if::cfg_if! {
+if #[cfg(feature = "ssr")] {
+    use super::admin::require_admin;
+    use super::list_bookings::fetch_bookings;
+    use crate::error::ServiceError;
+    use crate::i18n::use_i18n;
+    use crate::store::JsonStore;
+    use indexmap::IndexMap;
+    use crate::money::Totals;
+    use std::{collections::HashMap, sync::OnceLock};
+    use tracing::*;
+}}
+
+#[cfg(feature = "ssr")]
+fn contacts_store() -> &'static JsonStore<Contact> {
+    static STORE: OnceLock<JsonStore<Contact>> = OnceLock::new();
+    STORE.get_or_init(|| JsonStore::open("contacts"))
+}
+
+/// Remembers a booker's details, and the dietary choices on their first ticket, for next time.
+/// Only made once a booking is confirmed, so abandoned checkouts aren't remembered as attendees.
+#[cfg(feature = "ssr")]
+pub(crate) fn record_contact(customer_id: &str, booking: &NewBooking) {
+    let store = contacts_store();
+    let existing = store
+        .values()
+        .into_iter()
+        .find(|c| c.id == customer_id || c.merged_ids.iter().any(|id| id == customer_id))
+        .unwrap_or_default();
+
+    let id = if existing.id.is_empty() { customer_id.to_string() } else { existing.id };
+    let contact = Contact {
+        id: id.clone(),
+        name: booking.contact.name.clone(),
+        email: booking.contact.email.clone(),
+        phone_no: booking.contact.phone_no.clone(),
+        marketing_consent: booking.contact.marketing_consent,
+        dietary: booking
+            .tickets
+            .first()
+            .map(Ticket::dietary)
+            .unwrap_or(existing.dietary),
+        merged_ids: existing.merged_ids,
+    };
+
+    if let Err(e) = store.insert(id, contact) {
+        warn!(error = %e, "failed to save contact");
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) fn find_contact_by_email(email: &str) -> Option<Contact> {
+    let email = normalise_email(email);
+    contacts_store()
+        .values()
+        .into_iter()
+        .find(|c| normalise_email(&c.email) == email)
+}
+
+#[server(ListContacts, "/api")]
+pub async fn list_contacts() -> Result<Vec<ContactSummary>, ServerFnError> {
+    require_admin()?;
+    info!("listing contacts");
+    let known = contacts_store().values();
+    let merged_into = known
+        .iter()
+        .flat_map(|c| c.merged_ids.iter().map(|m| (m.clone(), c.id.clone())))
+        .collect::<HashMap<_, _>>();
+
+    let mut summaries = known
+        .into_iter()
+        .map(|contact| {
+            let summary = ContactSummary {
+                contact: contact.clone(),
+                bookings: vec![],
+                total_spend: Totals::default(),
+                possible_duplicates: vec![],
+            };
+            (contact.id, summary)
+        })
+        .collect::<IndexMap<_, _>>();
+
+    for booking in fetch_bookings().await? {
+        let id = merged_into
+            .get(&booking.contact.id)
+            .cloned()
+            .unwrap_or_else(|| booking.contact.id.clone());
+
+        let summary = summaries.entry(id.clone()).or_insert_with(|| ContactSummary {
+            contact: contact_from_booking(id, &booking),
+            bookings: vec![],
+            total_spend: Totals::default(),
+            possible_duplicates: vec![],
+        });
+        summary.total_spend.push(booking.paid());
+        summary.bookings.push(booking);
+    }
+
+    let keys = summaries
+        .values()
+        .map(|s| {
+            (
+                s.contact.id.clone(),
+                normalise_email(&s.contact.email),
+                normalise_phone(&s.contact.phone_no),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (id, email, phone) in keys.iter() {
+        summaries[id].possible_duplicates = keys
+            .iter()
+            .filter(|(other_id, other_email, other_phone)| {
+                other_id != id
+                    && ((!email.is_empty() && email == other_email) || (!phone.is_empty() && phone == other_phone))
+            })
+            .map(|(other_id, _, _)| other_id.clone())
+            .collect();
+    }
+
+    let mut contacts = summaries.into_values().collect::<Vec<_>>();
+    contacts.sort_by_key(|s| s.contact.name.to_lowercase());
+    Ok(contacts)
+}
+
+#[server(SaveContact, "/api")]
+pub async fn save_contact(contact: Contact) -> Result<(), ServerFnError> {
+    require_admin()?;
+    info!(id = %contact.id, "saving contact");
+    contacts_store()
+        .insert(contact.id.clone(), contact)
+        .map_err(ServiceError::unexpected)?;
+    Ok(())
+}
+
+/// Folds the contact with id `duplicate` into the one with id `keep`, so that bookings
+/// made under either show up against `keep`.
+#[server(MergeContacts, "/api")]
+pub async fn merge_contacts(keep: String, duplicate: String) -> Result<(), ServerFnError> {
+    require_admin()?;
+    info!(keep = %keep, duplicate = %duplicate, "merging contacts");
+    let unknown = || ServiceError::Validation(use_i18n().t("contact-unknown"));
+    let mut keep = find_contact(&keep).await?.ok_or_else(unknown)?;
+    let duplicate = find_contact(&duplicate).await?.ok_or_else(unknown)?;
+    if keep.id == duplicate.id {
+        return Err(unknown().into());
+    }
+
+    keep.merged_ids.push(duplicate.id.clone());
+    keep.merged_ids.extend(duplicate.merged_ids);
+    keep.marketing_consent |= duplicate.marketing_consent;
+    if keep.dietary == DietaryPreferences::default() {
+        keep.dietary = duplicate.dietary;
+    }
+
+    let store = contacts_store();
+    store
+        .insert(keep.id.clone(), keep)
+        .and_then(|_| store.remove(&duplicate.id))
+        .map_err(ServiceError::unexpected)?;
+    Ok(())
+}
+
+/// The contact with `id`, as we've recorded them or, if they've only booked before we
+/// started recording contacts, as their booking has them.
+#[cfg(feature = "ssr")]
+async fn find_contact(id: &str) -> Result<Option<Contact>, ServerFnError> {
+    if let Some(contact) = contacts_store().get(id) {
+        return Ok(Some(contact));
+    }
+    Ok(fetch_bookings()
+        .await?
+        .iter()
+        .find(|b| b.contact.id == id)
+        .map(|b| contact_from_booking(id.to_string(), b)))
+}
+
+#[cfg(feature = "ssr")]
+fn contact_from_booking(id: String, booking: &Booking) -> Contact {
+    Contact {
+        id,
+        name: booking.contact.name.clone(),
+        email: booking.contact.email.clone(),
+        phone_no: booking.contact.phone_no.clone(),
+        marketing_consent: false,
+        dietary: booking.tickets.first().map(Ticket::dietary).unwrap_or_default(),
+        merged_ids: vec![],
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn normalise_email(email: &str) -> String { email.trim().to_lowercase() }
+
+/// Contacts keep their numbers in international form already, so there's no region to go on.
+#[cfg(feature = "ssr")]
+fn normalise_phone(phone: &str) -> String {
+    to_e164(phone, "").unwrap_or_else(|| phone.chars().filter(|c| c.is_ascii_digit()).collect())
+}
@@ -1,12 +1,23 @@
 use crate::model::*;
 use leptos::*;
-use std::env;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
+use super::checkouts::{
+    attempt_key, confirm_booking, idempotency_key, reusable_checkout, track_checkout,
+    ConfirmOnPayment,
+};
+use super::admin::require_admin;
+use super::customers::find_or_create_customer;
+use super::discounts::{hold_discount, release_discount};
+use super::validation::validate_booking;
+use crate::error::ServiceError;
+use crate::i18n::use_i18n;
+use crate::payments::{provider_for, square, CheckoutRequest};
 use crate::square_api;
+use crate::square_client::square;
 use log::*;
-use sanitizer::prelude::*;
+use rust_decimal::prelude::*;
 use std::collections::HashMap;
     }
 }
@@ -15,141 +26,281 @@ use std::collections::HashMap;
 pub async fn create_payment_link(booking: NewBooking) -> Result<String, ServerFnError> {
     info!("creating payment link for booking: {:?}", booking);
 
-    let phone_number =
-        booking
-            .contact
-            .phone_number()?
-            .format()
-            .mode(phonenumber::Mode::E164)
-            .to_string();
-
-    let req = square_api::CreatePaymentLinkRequest {
-        idempotency_key: uuid::Uuid::new_v4().to_string(),
-        description: "Little Stukeley Christmas Dinner".to_string(),
-        order: build_order(&booking),
-        checkout_options: Some(square_api::CheckoutOptions {
-            allow_tipping: false,
-            ask_for_shipping_address: false,
-            enable_coupon: false,
-            enable_loyalty: false,
-            redirect_url: "".to_string(),
-        }),
-        pre_populated_data: Some(square_api::PrePopulatedData {
-            buyer_address: None,
-            buyer_email: Some(booking.contact.email),
-            buyer_phone_number: Some(phone_number),
-        }),
-    };
-
-    let req = build_request("online-checkout/payment-links").json(&req);
-
-    info!("request: {:?}", req);
+    let mut booking = booking;
+    booking.contact.normalise_phone();
+    let event = validate_booking(&booking)?;
 
-    let res = req.send().await.map_err(|e| {
-        warn!("failed to call square api: {}", e);
-        e
-    })?;
+    let draft_key = idempotency_key(&booking);
+    if let Some(existing) = reusable_checkout(&draft_key).await {
+        info!("reusing payment link for order {}", existing.order_id);
+        return Ok(existing.url);
+    }
 
-    if !res.status().is_success() {
-        let error_body = res.text().await?;
-        return Err(ServerFnError::ServerError(error_body));
+    // The code's use is held until the booking is paid for, or given back if it isn't.
+    let discount = booking
+        .discount
+        .as_ref()
+        .map(|d| hold_discount(&event, &d.code))
+        .transpose()?;
+    let checkout = async {
+        let customer_id = find_or_create_customer(&booking.contact).await?;
+        let checkout = provider_for(&event.payment)
+            .create_checkout(CheckoutRequest {
+                booking: &booking,
+                event: &event,
+                customer_id: customer_id.clone(),
+                discount: discount.as_ref(),
+                idempotency_key: attempt_key(&draft_key),
+            })
+            .await?;
+        Ok::<_, ServerFnError>((customer_id, checkout))
     }
+    .await;
+    let (customer_id, checkout) = match checkout {
+        Ok(made) => made,
+        Err(e) => {
+            if let Some(d) = &discount {
+                release_discount(&d.code);
+            }
+            return Err(e);
+        }
+    };
 
-    let parsed_res = res.json::<square_api::Welcome>().await?;
-    return Ok(parsed_res.payment_link.long_url);
+    let confirm = ConfirmOnPayment {
+        customer_id,
+        booking,
+        discount: discount.map(|d| d.code),
+    };
+    if checkout.awaiting_payment {
+        track_checkout(&draft_key, &checkout, confirm);
+    } else {
+        confirm_booking(&checkout.order_id, &confirm);
+    }
+    Ok(checkout.url)
 }
 
+/// Books without taking payment, which only a signed in admin may do.
 #[server(CreateOrder, "/api")]
 pub async fn create_order(booking: NewBooking) -> Result<String, ServerFnError> {
+    require_admin()?;
     info!("creating order for booking: {:?}", booking);
 
-    let resp = {
-        let req = square_api::CreateOrderRequest {
-            idempotency_key: uuid::Uuid::new_v4().to_string(),
-            order: build_order(&booking),
-        };
-
-        info!("req_payload {:?}", req);
-        let req = build_request("orders").json(&req);
-
-        info!("request: {:?}", req);
-
-        let res = req.send().await.map_err(|e| {
-            warn!("failed to call square api: {}", e);
-            e
-        })?;
-
-        if res.status().is_success() {
-            let parsed_res = res.json::<square_api::CreateOrderResponse>().await?;
-            info!("order created: {:?}", parsed_res.order);
-            return Ok(parsed_res.order.id);
+    let mut booking = booking;
+    booking.contact.normalise_phone();
+    let event = validate_booking(&booking)?;
+    let discount = booking
+        .discount
+        .as_ref()
+        .map(|d| hold_discount(&event, &d.code))
+        .transpose()?;
+    let order = async {
+        let customer_id = find_or_create_customer(&booking.contact).await?;
+        let order_id = square::create_order(&CheckoutRequest {
+            booking: &booking,
+            event: &event,
+            customer_id: customer_id.clone(),
+            discount: discount.as_ref(),
+            idempotency_key: idempotency_key(&booking),
+        })
+        .await?;
+        Ok::<_, ServerFnError>((customer_id, order_id))
+    }
+    .await;
+    let (customer_id, order_id) = match order {
+        Ok(made) => made,
+        Err(e) => {
+            if let Some(d) = &discount {
+                release_discount(&d.code);
+            }
+            return Err(e);
         }
-
-        let error_body = res.text().await?;
-        Err(ServerFnError::ServerError(error_body))
     };
-    if let Err(e) = resp.as_ref() {
-        warn!("error generating payment link: {}", e.to_string())
-    };
-    resp
-}
 
-#[cfg(feature = "ssr")]
-fn build_request(method: &str) -> reqwest::RequestBuilder {
-    let endpoint = env::var("SQUARE_ENDPOINT").expect("Error: SQUARE_API_KEY variable not found");
-    let api_key = env::var("SQUARE_API_KEY").expect("Error: SQUARE_API_KEY variable not found");
-
-    reqwest::Client::new()
-        .post(format!("https://{}/v2/{}", endpoint, method))
-        .header(reqwest::header::CONTENT_TYPE, "application/json")
-        .header(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {}", api_key),
-        )
+    confirm_booking(
+        &order_id,
+        &ConfirmOnPayment {
+            customer_id,
+            booking,
+            discount: discount.map(|d| d.code),
+        },
+    );
+    Ok(order_id)
 }
 
 #[cfg(feature = "ssr")]
-fn build_order(booking: &NewBooking) -> square_api::NewOrder {
-    let location_id =
-        env::var("SQUARE_LOCATION_ID").expect("Error: SQUARE_LOCATION_ID variable not found");
-
-    let item_id = env::var("SQUARE_ITEM_ID").expect("Error: SQUARE_LOCATION_ID variable not found");
+pub(crate) fn build_order(
+    booking: &NewBooking,
+    event: &Event,
+    customer_id: String,
+    discount: Option<&DiscountCode>,
+) -> Result<square_api::NewOrder, ServiceError> {
+    let square = square()?;
 
-    let catalog_version = env::var("SQUARE_CATALOG_VERSION")
-        .expect("Error: SQUARE_CATALOG_VERSION variable not found");
-    let catalog_version = catalog_version.parse::<i64>().unwrap();
+    // Percentage discounts are applied item by item so they never come off a donation.
+    let applied_discounts = || {
+        discount
+            .filter(|d| matches!(d.kind, DiscountKind::Percentage(_)))
+            .map(|_| square_api::AppliedDiscount {
+                discount_uid: "discount-code".to_string(),
+            })
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
 
-    let mut sanitizer = StringSanitizer::from(booking.contact.name.clone());
-    sanitizer.trim().to_snake_case();
-    let customer_id = sanitizer.get();
+    let mut line_items = ticket_groups(&booking.tickets)
+        .into_iter()
+        .map(|group| {
+            // Charged as the event sells the ticket, whatever the browser sent.
+            let name = &group[0].ticket_type.name;
+            let Some(tt) = event.ticket_types.clone().find(name.as_str()) else {
+                return Err(ServiceError::Validation(
+                    use_i18n().t_with("ticket-not-on-sale", &[("ticket", name.clone().into())]),
+                ));
+            };
+            if group[0].bundle.is_some() {
+                return Ok(square_api::NewLineItem {
+                    applied_discounts: applied_discounts(),
+                    ..bundle_line_item(&group, &tt)
+                });
+            }
 
-    let line_items =
-        booking
-            .tickets
-            .iter()
-            .map(|t| square_api::NewLineItem {
+            let t = group[0];
+            let mut metadata = HashMap::from([
+                ("gluten_free".to_string(), t.gluten_free.to_string()),
+                ("vegeterrible".to_string(), t.vegetarian.to_string()),
+                (
+                    "dietary_requirements".to_string(),
+                    if t.dietary_requirements.is_empty() {
+                        "none".to_string()
+                    } else {
+                        t.dietary_requirements.clone()
+                    },
+                ),
+            ]);
+            // Square won't take an empty metadata value.
+            if !t.attendee_name.trim().is_empty() {
+                metadata.insert("attendee_name".to_string(), t.attendee_name.trim().to_string());
+            }
+            Ok(square_api::NewLineItem {
                 quantity: "1".to_string(),
-                catalog_version, //todo: t.ticket_type.square_catalog_version,
-                catalog_object_id: item_id.clone(), //todo: t.ticket_type.square_item_id.clone(),
-                metadata: HashMap::from([
-                    ("gluten_free".to_string(), t.gluten_free.to_string()),
-                    ("vegeterrible".to_string(), t.vegetarian.to_string()),
-                    (
-                        "dietary_requirements".to_string(),
-                        if t.dietary_requirements.is_empty() {
-                            "none".to_string()
-                        } else {
-                            t.dietary_requirements.clone()
-                        },
-                    ),
-                ]),
+                catalog_version: Some(tt.square_catalog_version),
+                catalog_object_id: Some(tt.square_item_id.clone()),
+                applied_discounts: applied_discounts(),
+                metadata,
+                ..Default::default()
             })
-            .collect::<Vec<_>>();
+        })
+        .chain(
+            booking
+                .add_ons
+                .iter()
+                .filter(|o| o.quantity > 0)
+                .map(|o| {
+                    // Sold as the event sells it, whatever the browser sent.
+                    let Some(add_on) = event.add_ons.iter().find(|a| a.name == o.add_on.name) else {
+                        return Err(ServiceError::Validation(
+                            use_i18n().t_with("add-on-unavailable", &[("add_on", o.add_on.name.clone().into())]),
+                        ));
+                    };
+                    Ok(square_api::NewLineItem {
+                        quantity: o.quantity.to_string(),
+                        catalog_version: Some(add_on.square_catalog_version),
+                        catalog_object_id: Some(add_on.square_item_id.clone()),
+                        applied_discounts: applied_discounts(),
+                        metadata: HashMap::from([("add_on".to_string(), add_on.name.clone())]),
+                        ..Default::default()
+                    })
+                }),
+        )
+        .collect::<Result<Vec<_>, ServiceError>>()?;
+
+    if let Donations::Fund(fund) = &event.donations {
+        if booking.donation.amount > Decimal::ZERO {
+            line_items.push(square_api::NewLineItem {
+                quantity: "1".to_string(),
+                name: Some(use_i18n().t_with("donation-to", &[("fund", fund.clone().into())])),
+                base_price_money: Some(booking.donation.into()),
+                metadata: HashMap::from([("donation".to_string(), "true".to_string())]),
+                ..Default::default()
+            });
+        }
+    }
+
+    let discounts = discount
+        .into_iter()
+        .map(|d| {
+            let (discount_type, percentage, amount_money, scope) = match d.kind {
+                DiscountKind::Percentage(pct) => (
+                    square_api::DiscountType::FixedPercentage,
+                    Some(pct.normalize().to_string()),
+                    None,
+                    "LINE_ITEM",
+                ),
+                // Square takes an order-wide amount off the donation too, so it's capped
+                // at what the tickets and add-ons come to, as the booking summary is.
+                DiscountKind::Fixed(_) => (
+                    square_api::DiscountType::FixedAmount,
+                    None,
+                    Some(d.amount_off(booking.totals().subtotal).into()),
+                    "ORDER",
+                ),
+            };
+            square_api::NewOrderDiscount {
+                uid: "discount-code".to_string(),
+                name: format!("{} ({})", d.code, use_i18n().discount(d)),
+                discount_type,
+                percentage,
+                amount_money,
+                scope: scope.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
 
-    square_api::NewOrder {
+    Ok(square_api::NewOrder {
         customer_id: Some(customer_id),
-        location_id,
+        location_id: square.location_id().to_string(),
         line_items,
+        discounts,
+        metadata: HashMap::from([("event_id".to_string(), booking.event_id.clone())]),
+    })
+}
+
+/// The longest value Square will keep in a line item's metadata, in characters.
+#[cfg(feature = "ssr")]
+pub(crate) const MAX_METADATA_VALUE: usize = 255;
+
+/// Square allows ten metadata entries on a line item, and a bundle's has its
+/// `bundle_size` as well as one for each ticket.
+#[cfg(feature = "ssr")]
+pub(crate) const MAX_BUNDLE_MEMBERS: usize = 9;
+
+/// A single line item for a whole bundle of type `tt`, charged at the bundle's
+/// catalog price, with each ticket's dietary details kept in the metadata.
+#[cfg(feature = "ssr")]
+fn bundle_line_item(group: &[&Ticket], tt: &TicketType) -> square_api::NewLineItem {
+    let mut metadata = HashMap::from([("bundle_size".to_string(), group.len().to_string())]);
+    for (i, t) in group.iter().enumerate() {
+        metadata.insert(format!("member_{}", i), bundle_member_metadata(t));
+    }
+
+    square_api::NewLineItem {
+        quantity: "1".to_string(),
+        catalog_version: Some(tt.square_catalog_version),
+        catalog_object_id: Some(tt.square_item_id.clone()),
+        metadata,
+        ..Default::default()
     }
 }
 
+/// What's kept about one ticket of a bundle in its line item's metadata.
+#[cfg(feature = "ssr")]
+pub(crate) fn bundle_member_metadata(t: &Ticket) -> String {
+    let member = square_api::BundleMemberMetadata {
+        label: t.bundle.as_ref().map(|b| b.label.clone()).unwrap_or_default(),
+        attendee_name: t.attendee_name.trim().to_string(),
+        vegetarian: t.vegetarian,
+        gluten_free: t.gluten_free,
+        dietary_requirements: t.dietary_requirements.clone(),
+    };
+    serde_json::to_string(&member).unwrap_or_default()
+}
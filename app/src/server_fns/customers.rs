@@ -0,0 +1,73 @@
+use crate::customer_cache::{contact_from_customer, customer_cache};
+use crate::error::ServiceError;
+use crate::i18n::use_i18n;
+use crate::model::*;
+use crate::square_api;
+use crate::square_client::square;
+use log::*;
+
+/// Finds the Square customer for a booking contact, matching on email address
+/// and then phone number so repeat attendees keep the same customer across
+/// events, and creates one if they haven't booked before.
+pub(crate) async fn find_or_create_customer(contact: &BookingContact) -> Result<String, ServiceError> {
+    let phone_number = contact
+        .phone_number()
+        .map_err(|_| ServiceError::Validation(use_i18n().t("phone-number-invalid")))?
+        .format()
+        .mode(phonenumber::Mode::E164)
+        .to_string();
+
+    let by_email = square_api::CustomerFilter {
+        email_address: Some(square_api::CustomerTextFilter {
+            exact: contact.email.trim().to_string(),
+        }),
+        ..Default::default()
+    };
+    let by_phone = square_api::CustomerFilter {
+        phone_number: Some(square_api::CustomerTextFilter {
+            exact: phone_number.clone(),
+        }),
+        ..Default::default()
+    };
+
+    for filter in [by_email, by_phone] {
+        if let Some(customer) = search_customers(filter).await? {
+            info!("found existing customer {}", customer.id);
+            customer_cache().insert(contact_from_customer(&customer));
+            return Ok(customer.id);
+        }
+    }
+
+    let (given_name, family_name) = split_name(&contact.name);
+    let req = square_api::CreateCustomerRequest {
+        idempotency_key: uuid::Uuid::new_v4().to_string(),
+        given_name: Some(given_name),
+        family_name,
+        email_address: Some(contact.email.trim().to_string()),
+        phone_number: Some(phone_number),
+    };
+
+    let customer = square()?.create_customer(&req).await?;
+    info!("created customer {}", customer.id);
+    customer_cache().insert(contact_from_customer(&customer));
+    Ok(customer.id)
+}
+
+async fn search_customers(filter: square_api::CustomerFilter) -> Result<Option<square_api::Customer>, ServiceError> {
+    let req = square_api::SearchCustomersRequest {
+        query: Some(square_api::CustomerQuery { filter }),
+        limit: 1,
+        ..Default::default()
+    };
+
+    let res = square()?.search_customers(&req).await?;
+    Ok(res.customers.into_iter().next())
+}
+
+/// Splits a full name into Square's given and family names at the last space.
+fn split_name(name: &str) -> (String, Option<String>) {
+    match name.trim().rsplit_once(char::is_whitespace) {
+        Some((given, family)) => (given.trim().to_string(), Some(family.to_string())),
+        None => (name.trim().to_string(), None),
+    }
+}
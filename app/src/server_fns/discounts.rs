@@ -0,0 +1,134 @@
+use crate::model::*;
+use leptos::*;
+
+cfg_if::cfg_if! {
+if #[cfg(feature = "ssr")] {
+    use super::admin::require_admin;
+    use crate::error::ServiceError;
+    use crate::events::find_event;
+    use crate::i18n::use_i18n;
+    use crate::store::JsonStore;
+    use chrono::Utc;
+    use std::sync::OnceLock;
+    use tracing::*;
+}}
+
+#[cfg(feature = "ssr")]
+fn discounts_store() -> &'static JsonStore<DiscountCode> {
+    static STORE: OnceLock<JsonStore<DiscountCode>> = OnceLock::new();
+    STORE.get_or_init(|| JsonStore::open("discount_codes"))
+}
+
+/// Looks up a discount code and checks it can be used on `event`.
+#[cfg(feature = "ssr")]
+pub(crate) fn valid_discount(event: &Event, code: &str) -> Result<DiscountCode, ServiceError> {
+    let i18n = use_i18n();
+    let discount = discounts_store()
+        .get(&DiscountCode::normalise(code))
+        .ok_or_else(|| ServiceError::Validation(i18n.t("discount-unknown")))?;
+    discount
+        .check(event, Utc::now())
+        .map_err(|id| ServiceError::Validation(i18n.t(id)))?;
+    Ok(discount)
+}
+
+/// Checks a discount code can be used on `event` and sets one of its uses aside
+/// for a booking, in one step, so two bookings can't both take its last use.
+#[cfg(feature = "ssr")]
+pub(crate) fn hold_discount(event: &Event, code: &str) -> Result<DiscountCode, ServiceError> {
+    let i18n = use_i18n();
+    let mut checked = Err("discount-unknown");
+    let held = discounts_store()
+        .update(&DiscountCode::normalise(code), |d| {
+            checked = d.check(event, Utc::now());
+            if checked.is_ok() {
+                d.held += 1;
+            }
+        })
+        .map_err(ServiceError::unexpected)?;
+    checked.map_err(|id| ServiceError::Validation(i18n.t(id)))?;
+    held.ok_or_else(|| ServiceError::Validation(i18n.t("discount-unknown")))
+}
+
+/// Counts a held use of a discount code as used, once its booking is confirmed.
+#[cfg(feature = "ssr")]
+pub(crate) fn redeem_discount(code: &str) {
+    let redeem = |d: &mut DiscountCode| {
+        d.held = d.held.saturating_sub(1);
+        d.uses += 1;
+    };
+    if let Err(e) = discounts_store().update(code, redeem) {
+        warn!(code = code, error = %e, "failed to record discount use");
+    }
+}
+
+/// Gives back a held use of a discount code when its booking isn't made after all.
+#[cfg(feature = "ssr")]
+pub(crate) fn release_discount(code: &str) {
+    if let Err(e) = discounts_store().update(code, |d| d.held = d.held.saturating_sub(1)) {
+        warn!(code = code, error = %e, "failed to release discount use");
+    }
+}
+
+#[server(CheckDiscountCode, "/api")]
+pub async fn check_discount_code(event_id: String, code: String) -> Result<DiscountCode, ServerFnError> {
+    let event = find_event(&event_id)
+        .ok_or_else(|| ServiceError::Validation(use_i18n().t("discount-wrong-event")))?;
+    Ok(valid_discount(&event, &code)?)
+}
+
+#[server(ListDiscountCodes, "/api")]
+pub async fn list_discount_codes() -> Result<Vec<DiscountCode>, ServerFnError> {
+    require_admin()?;
+    let mut codes = discounts_store().values();
+    codes.sort_by(|a, b| (&a.event_id, &a.code).cmp(&(&b.event_id, &b.code)));
+    Ok(codes)
+}
+
+/// Adds a discount code, or changes the terms of an existing one. How many times
+/// it's been used is only ever counted here, so it's kept as it is.
+#[server(SaveDiscountCode, "/api")]
+pub async fn save_discount_code(discount: DiscountCode) -> Result<(), ServerFnError> {
+    require_admin()?;
+    let i18n = use_i18n();
+    let code = DiscountCode::normalise(&discount.code);
+    if code.is_empty() {
+        return Err(ServiceError::Validation(i18n.t("discount-code-required")).into());
+    }
+    let event = find_event(&discount.event_id)
+        .ok_or_else(|| ServiceError::Validation(i18n.t("discount-unknown-event")))?;
+    discount
+        .check_terms(&event)
+        .map_err(|id| ServiceError::Validation(i18n.t(id)))?;
+
+    info!(code = code, "saving discount code");
+    let store = discounts_store();
+    let updated = store
+        .update(&code, |d| {
+            d.event_id = discount.event_id.clone();
+            d.kind = discount.kind.clone();
+            d.max_uses = discount.max_uses;
+            d.expires_at = discount.expires_at;
+        })
+        .map_err(ServiceError::unexpected)?;
+    if updated.is_none() {
+        let new = DiscountCode {
+            code: code.clone(),
+            uses: 0,
+            held: 0,
+            ..discount
+        };
+        store.insert(code, new).map_err(ServiceError::unexpected)?;
+    }
+    Ok(())
+}
+
+#[server(DeleteDiscountCode, "/api")]
+pub async fn delete_discount_code(code: String) -> Result<(), ServerFnError> {
+    require_admin()?;
+    info!(code = code, "deleting discount code");
+    discounts_store()
+        .remove(&code)
+        .map(|_| ())
+        .map_err(|e| ServiceError::unexpected(e).into())
+}
@@ -1,53 +1,51 @@
-use std::env;
-
 use crate::model::*;
 use anyhow::Result;
 use leptos::*;
 
+#[cfg(feature = "ssr")]
+use crate::error::ServiceError;
+#[cfg(feature = "ssr")]
+use crate::i18n::use_i18n;
 #[cfg(feature = "ssr")]
 use tracing::*;
 
 #[cfg(feature = "ssr")]
-struct EmailConfig {
-    host: String,
-    port: u16,
-    user: String,
-    password: String,
-}
+pub(crate) const SENDER: (&str, &str) = ("Philip Scott", "safetyfirstphil@gmail.com");
 
+/// Sends a message through the configured SMTP server.
 #[cfg(feature = "ssr")]
-impl Default for EmailConfig {
-    fn default() -> Self {
-        EmailConfig {
-            host: env::var("EMAIL_HOST").expect("EMAIL_HOST to be in environment"),
-            user: env::var("EMAIL_USER").expect("EMAIL_USER to be in environment"),
-            password: env::var("EMAIL_PASSWORD").expect("EMAIL_PASSWORD to be in environment"),
-            port: env::var("EMAIL_PORT")
-                .expect("EMAIL_PORT to be in environment")
-                .parse()
-                .expect("email port number to be u16"),
-        }
-    }
+pub(crate) async fn send_email(message: mail_send::mail_builder::MessageBuilder<'_>) -> Result<(), ServiceError> {
+    use mail_send::SmtpClientBuilder;
+
+    let cfg = crate::config::app_config()?.email;
+    SmtpClientBuilder::new(cfg.host, cfg.port)
+        .implicit_tls(false)
+        .credentials((cfg.user, cfg.password))
+        .connect()
+        .await?
+        .send(message)
+        .await?;
+    Ok(())
 }
 
 #[server(EmailBooking, "/api")]
 pub async fn email_booking(booking: Booking) -> Result<(), ServerFnError> {
     use css_inline::CSSInliner;
-    use mail_send::{mail_builder::MessageBuilder, SmtpClientBuilder};
-    use pluralizer::pluralize;
+    use mail_send::mail_builder::MessageBuilder;
 
     info!("emailing booking");
-    let cfg = EmailConfig::default();
+    let i18n = use_i18n();
     let tickets = booking.tickets.clone();
 
     let tickets_table = view! {
       <table class="table">
         <thead>
           <tr>
-            <th>Ticket Type</th>
-            <th>Vegetarian</th>
-            <th>Gluten Free</th>
-            <th>Notes</th>
+            <th>{i18n.t("ticket-type")}</th>
+            <th>{i18n.t("attendee")}</th>
+            <th>{i18n.t("vegetarian")}</th>
+            <th>{i18n.t("gluten-free")}</th>
+            <th>{i18n.t("email-notes")}</th>
           </tr>
         </thead>
         <tbody>
@@ -57,8 +55,9 @@ pub async fn email_booking(booking: Booking) -> Result<(), ServerFnError> {
                   view! {
                     <tr>
                       <td>{t.ticket_type.name.clone()}</td>
-                      <td>{if t.vegetarian { "yes" } else { "" }}</td>
-                      <td>{if t.gluten_free { "yes" } else { "" }}</td>
+                      <td>{t.attendee_name.clone()}</td>
+                      <td>{if t.vegetarian { i18n.t("yes") } else { String::new() }}</td>
+                      <td>{if t.gluten_free { i18n.t("yes") } else { String::new() }}</td>
                       <td>
                         {if t.dietary_requirements != "none" { t.dietary_requirements.clone() } else { "".to_string() }}
                       </td>
@@ -70,13 +69,43 @@ pub async fn email_booking(booking: Booking) -> Result<(), ServerFnError> {
       </table>
     };
 
+    let add_ons_table = (!booking.add_ons.is_empty()).then(|| {
+        view! {
+          <div class="block">
+            <h2 class="subtitle">{i18n.t("add-ons")}</h2>
+            <table class="table">
+              <thead>
+                <tr>
+                  <th>{i18n.t("add-on")}</th>
+                  <th>{i18n.t("quantity")}</th>
+                </tr>
+              </thead>
+              <tbody>
+                {booking
+                    .add_ons
+                    .iter()
+                    .map(|o| {
+                        view! {
+                          <tr>
+                            <td>{o.add_on.name.clone()}</td>
+                            <td>{o.quantity}</td>
+                          </tr>
+                        }
+                    })
+                    .collect_view()}
+              </tbody>
+            </table>
+          </div>
+        }
+    });
+
     let booking_table = view! {
       <table class="table">
         <thead>
           <tr>
-            <th>Name</th>
-            <th>EMail</th>
-            <th>Phone</th>
+            <th>{i18n.t("name")}</th>
+            <th>{i18n.t("email")}</th>
+            <th>{i18n.t("phone")}</th>
           </tr>
         </thead>
         <tbody>
@@ -91,13 +120,14 @@ pub async fn email_booking(booking: Booking) -> Result<(), ServerFnError> {
         view! {
           <div class="box">
             <div class="block">
-              <h2 class="subtitle">Booking By</h2>
+              <h2 class="subtitle">{i18n.t("email-booking-by")}</h2>
               {booking_table}
             </div>
             <div class="block">
-              <h2 class="subtitle">Tickets</h2>
+              <h2 class="subtitle">{i18n.t("tickets")}</h2>
               {tickets_table}
             </div>
+            {add_ons_table}
           </div>
         };
 
@@ -106,27 +136,19 @@ pub async fn email_booking(booking: Booking) -> Result<(), ServerFnError> {
     let styled = CSSInliner::options()
         .extra_css(Some(css.into()))
         .build()
-        .inline(rendered.as_ref())?;
+        .inline(rendered.as_ref())
+        .map_err(ServiceError::email)?;
 
     let message = MessageBuilder::new()
-        .from(("Philip Scott", "safetyfirstphil@gmail.com"))
+        .from(SENDER)
         .to(vec![("Philip Scott", "phil@safetyphil.com")])
-        .subject(format!(
-            "Xmas Dinner: {} booked by {}",
-            pluralize("ticket", tickets.len().try_into()?, true),
-            booking.contact.name.clone(),
+        .subject(i18n.t_with(
+            "email-subject",
+            &[("tickets", tickets.len().into()), ("name", booking.contact.name.clone().into())],
         ))
         .html_body(styled)
-        .text_body("Switch to HTML View");
+        .text_body(i18n.t("email-text-body"));
 
-    SmtpClientBuilder::new(cfg.host, cfg.port)
-        .implicit_tls(false)
-        .credentials((cfg.user, cfg.password))
-        .connect()
-        .await?
-        .send(message)
-        .await?;
-
-    Ok(())
+    Ok(send_email(message).await?)
 }
 
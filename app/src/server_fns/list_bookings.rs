@@ -1,57 +1,22 @@
 use crate::model::*;
 
 use leptos::*;
-use std::env;
 
 cfg_if::cfg_if! {
 if #[cfg(feature = "ssr")] {
-    use rust_decimal::Decimal;
-    use square_api::model::{SearchOrdersFilter, SearchOrdersQuery, SearchOrdersStateFilter, SearchOrdersSourceFilter};
-    use std::{str::FromStr, sync::Arc};
+    use std::{collections::HashMap, str::FromStr};
+    use crate::money::{Currency, Money};
     use tracing::*;
-    use anyhow::{anyhow, Result};
     use convert_case::{Case, Casing};
-    use futures::stream::{self, StreamExt};
+    use crate::customer_cache::customer_cache;
+    use super::admin::require_admin;
+    use crate::error::ServiceError;
+    use crate::square_api;
+    use crate::square_client::square;
 }}
 
 #[cfg(feature = "ssr")]
-struct Config {
-    endpoint: String,
-    api_key: String,
-    location_id: String,
-}
-
-#[cfg(feature = "ssr")]
-impl Default for Config {
-    fn default() -> Self {
-        let endpoint = format!(
-            "https://{}",
-            env::var("SQUARE_ENDPOINT").expect("SQUARE_ENDPOINT to be in envrionment")
-        );
-        Config {
-            endpoint,
-            api_key: env::var("SQUARE_API_KEY").expect("SQUARE_API_KEY to be in environment"),
-            location_id: env::var("SQUARE_LOCATION_ID")
-                .expect("SQUARE_LOCATION_ID to be in environment"),
-        }
-    }
-}
-
-#[cfg(feature = "ssr")]
-fn get_client(cfg: &Config) -> Arc<square_api::SquareApiClient> {
-    let mut client = square_api::SquareApiClient::new(&cfg.endpoint);
-    client.client = client.client.default_header(
-        "Authorization".to_string(),
-        format!("Bearer {}", cfg.api_key),
-    );
-    Arc::new(client)
-}
-
-#[cfg(feature = "ssr")]
-async fn contact_from_order(
-    client: Arc<square_api::SquareApiClient>,
-    order: &square_api::model::Order,
-) -> BookingContact {
+fn contact_from_order(contacts: &HashMap<String, BookingContact>, order: &square_api::Order) -> BookingContact {
     let id = match order.customer_id {
         Some(ref id) => id.clone(),
         None => {
@@ -60,87 +25,132 @@ async fn contact_from_order(
         }
     };
 
-    let maybe_customer = client
-        .retrieve_customer(&id)
-        .await
-        .map_err(|e| anyhow!("customer search failed: {}", e))
-        .and_then(|resp| resp.customer.ok_or(anyhow!("no customer in response")))
-        .inspect_err(|e| warn!("error fetching customer {} {}", id, e.to_string()));
-
-    let customer = match maybe_customer {
-        Ok(c) => c,
-        Err(_) => {
-            return BookingContact {
-                id: id.clone(),
-                name: id.to_case(Case::Title),
-                ..Default::default()
-            };
-        }
-    };
-
-    BookingContact {
-        id: customer.id.unwrap_or_default(),
-        name: format!(
-            "{} {}",
-            customer.given_name.unwrap_or_default(),
-            customer.family_name.unwrap_or_default()
-        ),
-        email: customer.email_address.unwrap_or_default(),
-        phone_no: customer.phone_number.unwrap_or_default(),
-        event_id: "".to_string(),
+    match contacts.get(&id) {
+        Some(contact) => contact.clone(),
+        None => BookingContact {
+            id: id.clone(),
+            name: id.to_case(Case::Title),
+            ..Default::default()
+        },
     }
 }
 
 #[cfg(feature = "ssr")]
-async fn booking_from_order(
-    client: Arc<square_api::SquareApiClient>,
-    order: &square_api::model::Order,
-) -> Booking {
-    let contact = contact_from_order(client, order).await;
-
-    let tickets = order.line_items.iter().flatten().map(|line_item| Ticket {
-        booking_id: order.id.clone().unwrap_or_default(),
-        ticket_type: TicketType {
-            name: line_item.variation_name.clone().unwrap_or_default(),
-            price: Decimal::new(
-                line_item
-                    .base_price_money
-                    .as_ref()
-                    .and_then(|bp| bp.amount)
-                    .unwrap_or_default(),
-                2,
-            ),
-            square_item_id: line_item.catalog_object_id.clone().unwrap_or_default(),
-            square_catalog_version: line_item.catalog_version.unwrap_or_default(),
-        },
-        vegetarian: line_item.metadata_or_default("vegeterrible"),
-        gluten_free: line_item.metadata_or_default("gluten_free"),
-        dietary_requirements: line_item.metadata_or_default("dietary_requirements"),
-    });
-
-    let payment = match order.tenders.as_ref() {
-        Some(tenders) => {
-            BookingPayment::Card(
-                tenders
-                    .iter()
-                    .flat_map(|t| &t.amount_money)
-                    .filter_map(|m| m.amount)
-                    .map(|a| Decimal::new(a, 2))
-                    .sum(),
-            )
-        }
-        None => BookingPayment::NotPaid,
+fn booking_from_order(contacts: &HashMap<String, BookingContact>, order: &square_api::Order) -> Booking {
+    let contact = contact_from_order(contacts, order);
+
+    let booking_id = order.id.clone();
+    let currency = order.total_money.as_ref().map(|m| m.currency).unwrap_or_default();
+    let has_metadata = |line_item: &square_api::LineItem, key: &str| line_item.metadata.contains_key(key);
+    let line_items = order.line_items.iter();
+    let tickets = line_items
+        .clone()
+        .filter(|line_item| !has_metadata(line_item, "add_on") && !has_metadata(line_item, "donation"))
+        .flat_map(|line_item| tickets_from_line_item(&booking_id, line_item, currency));
+    let add_ons = line_items
+        .clone()
+        .filter(|line_item| has_metadata(line_item, "add_on"))
+        .map(|line_item| add_on_from_line_item(line_item, currency));
+
+    let donated = line_items
+        .filter(|line_item| has_metadata(line_item, "donation"))
+        .filter_map(|line_item| line_item.base_price_money.as_ref().map(|m| m.amount))
+        .sum::<i64>();
+    let tipped = order.total_tip_money.as_ref().map(|m| m.amount).unwrap_or_default();
+
+    let payment = if order.tenders.is_empty() {
+        BookingPayment::NotPaid
+    } else {
+        BookingPayment::Card(
+            order
+                .tenders
+                .iter()
+                .flat_map(|t| &t.amount_money)
+                .fold(Money::zero(currency), |paid, m| paid + Money::from(m)),
+        )
     };
 
     Booking {
-        id: order.id.clone().unwrap_or_default(),
-        event_id: "".to_string(),
+        id: order.id.clone(),
+        event_id: order.metadata_or_default("event_id"),
         contact: contact.clone(),
         tickets: tickets.collect(),
+        add_ons: add_ons.collect(),
+        donation: Money::from_minor_units(donated + tipped, currency),
         payment,
     }
 }
 
+/// Reads back the tickets a line item was created for; one, unless it's a bundle.
+#[cfg(feature = "ssr")]
+fn tickets_from_line_item(booking_id: &str, line_item: &square_api::LineItem, currency: Currency) -> Vec<Ticket> {
+    let ticket_type = TicketType {
+        name: line_item.variation_name.clone().unwrap_or_default(),
+        price: base_price(line_item, currency),
+        square_item_id: line_item.catalog_object_id.clone().unwrap_or_default(),
+        square_catalog_version: line_item.catalog_version.unwrap_or_default(),
+        on_sale_from: None,
+        on_sale_until: None,
+        bundle: vec![],
+    };
+
+    if !line_item.metadata.contains_key("bundle_size") {
+        return vec![Ticket {
+            booking_id: booking_id.to_string(),
+            ticket_type,
+            attendee_name: line_item.metadata.get("attendee_name").cloned().unwrap_or_default(),
+            vegetarian: line_item.metadata_or_default("vegeterrible"),
+            gluten_free: line_item.metadata_or_default("gluten_free"),
+            dietary_requirements: line_item.metadata_or_default("dietary_requirements"),
+            bundle: None,
+        }];
+    }
+
+    let bundle_id = line_item.uid.clone().unwrap_or_default();
+    let bundle_size: usize = line_item.metadata_or_default("bundle_size");
+    (0..bundle_size)
+        .map(|i| {
+            let member: String = line_item.metadata_or_default(&format!("member_{}", i));
+            let member = serde_json::from_str::<square_api::BundleMemberMetadata>(&member).unwrap_or_default();
+            Ticket {
+                booking_id: booking_id.to_string(),
+                ticket_type: ticket_type.clone(),
+                attendee_name: member.attendee_name,
+                vegetarian: member.vegetarian,
+                gluten_free: member.gluten_free,
+                dietary_requirements: member.dietary_requirements,
+                bundle: Some(BundleMember {
+                    bundle_id: bundle_id.clone(),
+                    label: member.label,
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+fn add_on_from_line_item(line_item: &square_api::LineItem, currency: Currency) -> AddOnOrder {
+    AddOnOrder {
+        add_on: AddOn {
+            name: line_item.metadata_or_default("add_on"),
+            price: base_price(line_item, currency),
+            square_item_id: line_item.catalog_object_id.clone().unwrap_or_default(),
+            square_catalog_version: line_item.catalog_version.unwrap_or_default(),
+            max_per_booking: None,
+        },
+        quantity: line_item.quantity.parse().unwrap_or_default(),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn base_price(line_item: &square_api::LineItem, currency: Currency) -> Money {
+    line_item
+        .base_price_money
+        .as_ref()
+        .map(Money::from)
+        .unwrap_or(Money::zero(currency))
+}
+
 #[cfg(feature = "ssr")]
 trait ExtractableMetadata {
     fn metadata_or_default<T>(&self, key: &str) -> T
@@ -150,60 +160,89 @@ trait ExtractableMetadata {
 }
 
 #[cfg(feature = "ssr")]
-impl ExtractableMetadata for square_api::model::OrderLineItem {
+impl ExtractableMetadata for square_api::LineItem {
+    fn metadata_or_default<T>(&self, key: &str) -> T
+    where
+        T: Default + FromStr,
+        <T as FromStr>::Err: std::fmt::Debug,
+    {
+        parse_metadata(&self.metadata, key)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl ExtractableMetadata for square_api::Order {
     fn metadata_or_default<T>(&self, key: &str) -> T
     where
         T: Default + FromStr,
         <T as FromStr>::Err: std::fmt::Debug,
     {
-        self.metadata
-            .as_ref()
-            .ok_or("no metadata".to_string())
-            .and_then(|md| md.get(key).ok_or("key not present".to_string()))
-            .and_then(|o| o.as_str().ok_or("value not string".to_string()))
-            .and_then(|o| o.parse::<T>().map_err(|e| format!("parse failed: {:?}", e)))
-            .unwrap_or_else(|e| {
-                warn!(key = key, error = e, "error parsing metadata");
-                T::default()
-            })
+        parse_metadata(&self.metadata, key)
     }
 }
 
+#[cfg(feature = "ssr")]
+fn parse_metadata<T>(metadata: &HashMap<String, String>, key: &str) -> T
+where
+    T: Default + FromStr,
+    <T as FromStr>::Err: std::fmt::Debug,
+{
+    metadata
+        .get(key)
+        .ok_or("key not present".to_string())
+        .and_then(|o| o.parse::<T>().map_err(|e| format!("parse failed: {:?}", e)))
+        .unwrap_or_else(|e| {
+            warn!(key = key, error = e, "error parsing metadata");
+            T::default()
+        })
+}
+
 #[server(ListBookings, "/api")]
 pub async fn list_bookings() -> Result<Vec<Booking>, ServerFnError> {
+    require_admin()?;
     info!("listing bookings");
-    warn!("working?");
-    let cfg = Config::default();
-    let client = get_client(&cfg);
-
-    let query = SearchOrdersQuery {
-        filter: Some(SearchOrdersFilter {
-            state_filter: Some(SearchOrdersStateFilter {
-                states: vec!["OPEN".to_string()],
-            }),
-            source_filter: Some(SearchOrdersSourceFilter {
-                source_names: Some(vec!["StukeleyHappenings".to_string()]),
-            }),
-            ..Default::default()
-        }),
-        ..Default::default()
+    fetch_bookings().await
+}
+
+/// Fetches every booking made through the site, across all events.
+#[cfg(feature = "ssr")]
+pub async fn fetch_bookings() -> Result<Vec<Booking>, ServerFnError> {
+    let square = square()?;
+    let mut req = square_api::SearchOrdersRequest {
+        location_ids: vec![square.location_id().to_string()],
+        query: square_api::SearchOrdersQuery {
+            filter: square_api::SearchOrdersFilter {
+                state_filter: Some(square_api::SearchOrdersStateFilter {
+                    states: vec!["OPEN".to_string()],
+                }),
+                source_filter: Some(square_api::SearchOrdersSourceFilter {
+                    source_names: vec!["StukeleyHappenings".to_string()],
+                }),
+            },
+        },
+        cursor: None,
     };
 
-    let resp = client
-        .search_orders()
-        .location_ids(vec![cfg.location_id.clone()])
-        .query(query.clone())
-        .await?;
+    let mut orders = vec![];
+    loop {
+        let resp = square.search_orders(&req).await.map_err(ServiceError::from)?;
+        orders.extend(resp.orders);
+        match resp.cursor {
+            Some(cursor) => req.cursor = Some(cursor),
+            None => break,
+        }
+    }
 
-    let bookings = stream::iter(resp.orders.unwrap_or_default())
-        .map(|order| {
-            let client = client.clone();
-            async move { booking_from_order(client, &order).await }
-        })
-        .buffered(10)
-        .collect::<Vec<_>>()
-        .await;
+    let customer_ids = orders
+        .iter()
+        .filter_map(|o| o.customer_id.clone())
+        .collect::<Vec<_>>();
+    let contacts = customer_cache().contacts(&customer_ids).await;
+
+    let bookings = orders
+        .iter()
+        .map(|order| booking_from_order(&contacts, order))
+        .collect::<Vec<_>>();
 
     Ok(bookings)
 }
-
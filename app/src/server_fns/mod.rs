@@ -1,8 +1,26 @@
+mod admin;
+#[cfg(feature = "ssr")]
+mod checkouts;
+mod contacts;
 mod create_payment_link;
+#[cfg(feature = "ssr")]
+mod customers;
+mod discounts;
 mod email_booking;
 mod list_bookings;
+mod returning_booker;
+#[cfg(feature = "ssr")]
+mod validation;
 
+pub use admin::*;
+#[cfg(feature = "ssr")]
+pub use checkouts::{cancel_abandoned_checkouts, cancel_checkouts_expired_by};
+#[cfg(feature = "ssr")]
+pub(crate) use checkouts::complete_checkout;
+pub use contacts::*;
 pub use create_payment_link::*;
+pub use discounts::*;
 pub use email_booking::*;
 pub use list_bookings::*;
+pub use returning_booker::*;
 
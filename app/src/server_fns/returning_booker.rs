@@ -0,0 +1,147 @@
+use crate::model::*;
+use leptos::*;
+
+cfg_if::cfg_if! {
+if #[cfg(feature = "ssr")] {
+    use super::contacts::find_contact_by_email;
+    use super::email_booking::{send_email, SENDER};
+    use crate::error::ServiceError;
+    use crate::i18n::use_i18n;
+    use crate::store::JsonStore;
+    use serde::{Deserialize, Serialize};
+    use std::{sync::OnceLock, time::{Duration, SystemTime, UNIX_EPOCH}};
+    use tracing::*;
+}}
+
+#[cfg(feature = "ssr")]
+const CODE_VALID_FOR: Duration = Duration::from_secs(15 * 60);
+/// Wrong guesses allowed before the code stops working, however many are sent.
+#[cfg(feature = "ssr")]
+const MAX_ATTEMPTS: u32 = 5;
+/// The soonest another code can be sent to the same address.
+#[cfg(feature = "ssr")]
+const RESEND_AFTER: Duration = Duration::from_secs(60);
+#[cfg(feature = "ssr")]
+const MAX_SENDS_PER_HOUR: usize = 5;
+
+#[cfg(feature = "ssr")]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BookingCode {
+    code: String,
+    expires_at: u64,
+    /// Wrong guesses at any of the codes sent, until the latest one expires.
+    attempts: u32,
+    /// When codes were sent to the address in the last hour.
+    #[serde(default)]
+    sent_at: Vec<u64>,
+}
+
+#[cfg(feature = "ssr")]
+impl BookingCode {
+    fn is_expired(&self, now: u64) -> bool { self.expires_at < now }
+}
+
+#[cfg(feature = "ssr")]
+fn codes_store() -> &'static JsonStore<BookingCode> {
+    static STORE: OnceLock<JsonStore<BookingCode>> = OnceLock::new();
+    STORE.get_or_init(|| JsonStore::open("booking_codes"))
+}
+
+#[cfg(feature = "ssr")]
+fn now_secs() -> u64 { SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() }
+
+/// Emails a one-time code to someone who has booked before. Succeeds whether
+/// or not we know the address, so the form doesn't reveal who has booked, and
+/// whether or not a code was sent, so it can't be used to flood an inbox.
+#[server(SendBookingCode, "/api")]
+pub async fn send_booking_code(email: String) -> Result<(), ServerFnError> {
+    use mail_send::mail_builder::MessageBuilder;
+
+    let contact = match find_contact_by_email(&email) {
+        Some(c) => c,
+        None => {
+            info!("no previous booking for code request");
+            return Ok(());
+        }
+    };
+
+    let key = contact.email.trim().to_lowercase();
+    let now = now_secs();
+    let earlier = codes_store().get(&key);
+    let sent_at = earlier
+        .as_ref()
+        .map(|c| c.sent_at.iter().copied().filter(|at| now < at + 60 * 60).collect::<Vec<_>>())
+        .unwrap_or_default();
+    // Wrong guesses count against every code sent until the latest expires, so
+    // asking for another doesn't earn more guesses.
+    let attempts = earlier
+        .filter(|c| !c.is_expired(now))
+        .map_or(0, |c| c.attempts);
+
+    let too_soon = sent_at.last().is_some_and(|at| now < at + RESEND_AFTER.as_secs());
+    if too_soon || sent_at.len() >= MAX_SENDS_PER_HOUR || attempts >= MAX_ATTEMPTS {
+        info!("not sending another booking code yet");
+        return Ok(());
+    }
+
+    let code = format!("{:06}", uuid::Uuid::new_v4().as_u128() % 1_000_000);
+    let entry = BookingCode {
+        code: code.clone(),
+        expires_at: now + CODE_VALID_FOR.as_secs(),
+        attempts,
+        sent_at: sent_at.into_iter().chain([now]).collect(),
+    };
+    codes_store()
+        .insert(key, entry)
+        .map_err(ServiceError::unexpected)?;
+
+    let i18n = use_i18n();
+    let body = i18n.t_with(
+        "booking-code-body",
+        &[
+            ("name", contact.name.clone().into()),
+            ("code", code.into()),
+            ("minutes", (CODE_VALID_FOR.as_secs() / 60).into()),
+        ],
+    );
+    let message = MessageBuilder::new()
+        .from(SENDER)
+        .to(vec![(contact.name.as_str(), contact.email.as_str())])
+        .subject(i18n.t("booking-code-subject"))
+        .text_body(format!("{}\n", body));
+
+    Ok(send_email(message).await?)
+}
+
+/// Checks a one-time code, returning the details from the booker's previous booking.
+#[server(VerifyBookingCode, "/api")]
+pub async fn verify_booking_code(email: String, code: String) -> Result<PreviousBooking, ServerFnError> {
+    let key = email.trim().to_lowercase();
+    let store = codes_store();
+    let invalid = || ServiceError::Validation(use_i18n().t("booking-code-wrong"));
+
+    let entry = store
+        .update(&key, |c| c.attempts += 1)
+        .map_err(ServiceError::unexpected)?
+        .ok_or_else(invalid)?;
+
+    // Kept even once it's no good, so the guesses and sends used up stay used up.
+    if entry.is_expired(now_secs()) || entry.attempts > MAX_ATTEMPTS {
+        return Err(invalid().into());
+    }
+    if entry.code != code.trim() {
+        return Err(invalid().into());
+    }
+
+    let _ = store.remove(&key);
+    let contact = find_contact_by_email(&email).ok_or_else(invalid)?;
+    info!(id = %contact.id, "returning booker verified");
+
+    Ok(PreviousBooking {
+        name: contact.name,
+        email: contact.email,
+        phone_no: contact.phone_no,
+        marketing_consent: contact.marketing_consent,
+        dietary: contact.dietary,
+    })
+}
@@ -0,0 +1,164 @@
+use super::create_payment_link::{bundle_member_metadata, MAX_BUNDLE_MEMBERS, MAX_METADATA_VALUE};
+use crate::error::{FieldErrors, ServiceError};
+use crate::events::find_event;
+use crate::i18n::{use_i18n, I18n};
+use crate::model::*;
+use chrono::Utc;
+use validator::Validate;
+
+/// Checks everything about a booking the browser could have got wrong, or been
+/// made to get wrong, returning the event it's for. Problems with what was
+/// entered come back together, keyed by field, so the form can show each one
+/// next to the control it's about.
+pub(crate) fn validate_booking(booking: &NewBooking) -> Result<Event, ServiceError> {
+    let i18n = use_i18n();
+    let event = find_event(&booking.event_id).ok_or_else(|| {
+        ServiceError::Validation(i18n.t_with("not-taking-bookings", &[("event", booking.event_id.clone().into())]))
+    })?;
+    let on_sale = event.ticket_types.on_sale(Utc::now());
+    if on_sale.clone().into_iter().next().is_none() {
+        return Err(ServiceError::Validation(
+            i18n.t_with("event-not-on-sale", &[("event", event.name.clone().into())]),
+        ));
+    }
+
+    let mut errors = FieldErrors::new();
+    check_contact(&booking.contact, &i18n, &mut errors);
+    check_tickets(booking, on_sale, &i18n, &mut errors);
+    check_add_ons(booking, &event, &i18n, &mut errors);
+    check_donation(booking, &event, &i18n, &mut errors);
+    if !errors.is_empty() {
+        return Err(ServiceError::InvalidFields(errors));
+    }
+
+    check_add_on_limits(booking, &event, &i18n)?;
+    Ok(event)
+}
+
+fn check_contact(contact: &BookingContact, i18n: &I18n, errors: &mut FieldErrors) {
+    let Err(invalid) = contact.validate() else {
+        return;
+    };
+    for field in invalid.field_errors().into_keys() {
+        let message = match field {
+            "name" => "name-required",
+            "email" => "enter-valid-email",
+            "phone_no" => "phone-invalid",
+            _ => "check-this",
+        };
+        errors.insert(format!("contact.{}", field), i18n.t(message));
+    }
+}
+
+/// Checks there's at least one ticket, that each is filled in properly, that
+/// each is of a type the event is selling now, at the price it's selling it for,
+/// and that tickets bought as a bundle make up a whole bundle of that type.
+fn check_tickets(booking: &NewBooking, on_sale: TicketTypes, i18n: &I18n, errors: &mut FieldErrors) {
+    if booking.tickets.is_empty() {
+        errors.insert("tickets".to_string(), i18n.t("add-a-ticket"));
+    }
+
+    for (i, ticket) in booking.tickets.iter().enumerate() {
+        if let Err(invalid) = ticket.validate() {
+            if let Some(message) = validation_messages(&invalid, i18n).into_values().next() {
+                errors.insert(format!("tickets.{}", i), message);
+            }
+        }
+        let name = &ticket.ticket_type.name;
+        let message = match on_sale.clone().find(name.as_str()) {
+            None => i18n.t_with("ticket-not-on-sale", &[("ticket", name.clone().into())]),
+            Some(tt)
+                if tt.price != ticket.ticket_type.price || tt.square_item_id != ticket.ticket_type.square_item_id =>
+            {
+                i18n.t_with(
+                    "ticket-price-changed",
+                    &[("ticket", name.clone().into()), ("price", i18n.money(&tt.price).into())],
+                )
+            }
+            Some(_) => continue,
+        };
+        errors.insert(format!("tickets.{}", i), message);
+    }
+
+    // Otherwise a bundle's one price could be stretched over tickets it doesn't cover.
+    let bundles = ticket_groups(&booking.tickets)
+        .into_iter()
+        .filter(|g| g[0].bundle.is_some());
+    for group in bundles {
+        let name = &group[0].ticket_type.name;
+        let whole = on_sale
+            .clone()
+            .find(name.as_str())
+            .is_some_and(|tt| tt.is_made_up_of(&group));
+        for (i, ticket) in booking.tickets.iter().enumerate() {
+            if !group.iter().any(|t| std::ptr::eq(*t, ticket)) {
+                continue;
+            }
+            // Each ticket's details go in the bundle's line item metadata, which Square limits.
+            let message = if !whole {
+                i18n.t_with("bundle-incomplete", &[("ticket", name.clone().into())])
+            } else if group.len() > MAX_BUNDLE_MEMBERS {
+                i18n.t_with("bundle-too-big", &[("ticket", name.clone().into())])
+            } else if bundle_member_metadata(ticket).chars().count() > MAX_METADATA_VALUE {
+                i18n.t("ticket-details-too-long")
+            } else {
+                continue;
+            };
+            errors.entry(format!("tickets.{}", i)).or_insert(message);
+        }
+    }
+}
+
+/// Checks each add-on is one the event sells, exactly as it sells it: same price,
+/// currency and catalog item.
+fn check_add_ons(booking: &NewBooking, event: &Event, i18n: &I18n, errors: &mut FieldErrors) {
+    for order in booking.add_ons.iter() {
+        let name = &order.add_on.name;
+        let message = match event.add_ons.iter().find(|a| a.name == *name) {
+            None => i18n.t_with("add-on-unavailable", &[("add_on", name.clone().into())]),
+            Some(add_on) if *add_on != order.add_on => i18n.t_with(
+                "add-on-changed",
+                &[("add_on", name.clone().into()), ("price", i18n.money(&add_on.price).into())],
+            ),
+            Some(_) => continue,
+        };
+        errors.insert(format!("add_ons.{}", name), message);
+    }
+}
+
+/// Checks a donation is only given where the event asks for one, in the event's currency.
+fn check_donation(booking: &NewBooking, event: &Event, i18n: &I18n, errors: &mut FieldErrors) {
+    if booking.donation.currency != event.currency {
+        errors.insert(
+            "donation".to_string(),
+            i18n.t_with("pay-in-currency", &[("currency", event.currency.code().into())]),
+        );
+    } else if booking.donation.amount.is_sign_negative() && !booking.donation.is_zero() {
+        errors.insert("donation".to_string(), i18n.t("donation-negative"));
+    } else if !booking.donation.is_zero() && !matches!(event.donations, Donations::Fund(_)) {
+        errors.insert("donation".to_string(), i18n.t("donations-not-taken"));
+    }
+}
+
+/// Checks no add-on is bought beyond its per-booking limit, however many times it's
+/// listed in the booking.
+fn check_add_on_limits(booking: &NewBooking, event: &Event, i18n: &I18n) -> Result<(), ServiceError> {
+    for add_on in event.add_ons.iter() {
+        let Some(max) = add_on.max_per_booking else {
+            continue;
+        };
+        let quantity = booking
+            .add_ons
+            .iter()
+            .filter(|o| o.add_on.name == add_on.name)
+            .map(|o| o.quantity)
+            .sum::<u32>();
+        if quantity > max {
+            return Err(ServiceError::Capacity(i18n.t_with(
+                "add-on-limit",
+                &[("max", max.into()), ("add_on", add_on.name.clone().into())],
+            )));
+        }
+    }
+    Ok(())
+}
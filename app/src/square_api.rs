@@ -1,22 +1,40 @@
+//! The Square API requests and responses we use. Responses only describe the
+//! fields we read, and are lenient about the rest.
+
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+pub use crate::money::Currency;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatePaymentLinkRequest {
+    pub idempotency_key: String,
+    pub order: NewOrder,
+    pub description: String,
+    pub pre_populated_data: Option<PrePopulatedData>,
+    pub checkout_options: Option<CheckoutOptions>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Welcome {
+pub struct CreatePaymentLinkResponse {
     pub payment_link: PaymentLink,
-    pub related_resources: RelatedResources,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PaymentLink {
     pub id: String,
-    pub version: i64,
-    pub description: String,
     pub order_id: String,
     pub url: String,
     pub long_url: String,
-    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletePaymentLinkResponse {
+    pub id: String,
+    /// The link's order, which Square cancels when the link is deleted.
+    #[serde(default)]
+    pub cancelled_order_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,21 +43,22 @@ pub struct CheckoutOptions {
     pub ask_for_shipping_address: bool,
     pub enable_coupon: bool,
     pub enable_loyalty: bool,
-    pub redirect_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant_support_email: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct RelatedResources {
-    pub orders: Vec<Order>,
+pub struct PrePopulatedData {
+    pub buyer_address: Option<Address>,
+    pub buyer_email: Option<String>,
+    pub buyer_phone_number: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CreatePaymentLinkRequest {
-    pub idempotency_key: String,
-    pub order: NewOrder,
-    pub description: String,
-    pub pre_populated_data: Option<PrePopulatedData>,
-    pub checkout_options: Option<CheckoutOptions>,
+pub struct Address {
+    //todo if needed
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,106 +68,313 @@ pub struct CreateOrderRequest {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CreateOrderResponse {
+pub struct OrderResponse {
     pub order: Order,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateOrderRequest {
+    pub idempotency_key: String,
+    pub order: OrderUpdate,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderUpdate {
+    pub location_id: String,
+    pub version: i64,
+    pub state: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchOrdersRequest {
+    pub location_ids: Vec<String>,
+    pub query: SearchOrdersQuery,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchOrdersQuery {
+    pub filter: SearchOrdersFilter,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchOrdersFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_filter: Option<SearchOrdersStateFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_filter: Option<SearchOrdersSourceFilter>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchOrdersStateFilter {
+    pub states: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchOrdersSourceFilter {
+    pub source_names: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchOrdersResponse {
+    #[serde(default)]
+    pub orders: Vec<Order>,
+    pub cursor: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewOrder {
     pub location_id: String,
     pub customer_id: Option<String>,
     pub line_items: Vec<NewLineItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub discounts: Vec<NewOrderDiscount>,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewOrderDiscount {
+    pub uid: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub discount_type: DiscountType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_money: Option<Money>,
+    pub scope: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub enum DiscountType {
+    #[serde(rename = "FIXED_PERCENTAGE")]
+    FixedPercentage,
+    #[serde(rename = "FIXED_AMOUNT")]
+    FixedAmount,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct NewLineItem {
     pub quantity: String,
-    pub catalog_object_id: String,
-    pub catalog_version: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalog_object_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalog_version: Option<i64>,
+    /// Names an ad hoc line item that isn't in the catalog, priced by `base_price_money`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_price_money: Option<Money>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub applied_discounts: Vec<AppliedDiscount>,
     pub metadata: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct PrePopulatedData {
-    pub buyer_address: Option<Address>,
-    pub buyer_email: Option<String>,
-    pub buyer_phone_number: Option<String>,
+pub struct AppliedDiscount {
+    pub discount_uid: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Address {
-    //todo if needed
+/// What we record in a bundle line item's metadata about each ticket in the bundle.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BundleMemberMetadata {
+    pub label: String,
+    #[serde(default)]
+    pub attendee_name: String,
+    pub vegetarian: bool,
+    pub gluten_free: bool,
+    pub dietary_requirements: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Order {
     pub id: String,
     pub location_id: String,
-    pub source: Source,
-    pub line_items: Vec<LineItem>,
-    pub fulfillments: Option<Vec<Fulfillment>>,
-    pub net_amounts: NetAmounts,
-    pub created_at: String,
-    pub updated_at: String,
+    pub customer_id: Option<String>,
     pub state: String,
     pub version: i64,
-    pub total_money: Money,
-    pub total_tax_money: Money,
-    pub total_discount_money: Money,
-    pub total_tip_money: Money,
-    pub total_service_charge_money: Money,
-    pub net_amount_due_money: Money,
+    #[serde(default)]
+    pub line_items: Vec<LineItem>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub tenders: Vec<Tender>,
+    pub total_money: Option<Money>,
+    pub total_tip_money: Option<Money>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LineItem {
+    pub uid: Option<String>,
+    pub name: Option<String>,
+    pub quantity: String,
+    pub catalog_object_id: Option<String>,
+    pub catalog_version: Option<i64>,
+    pub variation_name: Option<String>,
+    pub base_price_money: Option<Money>,
+    pub total_money: Option<Money>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Fulfillment {
-    pub uid: String,
-    #[serde(rename = "type")]
-    pub fulfillment_type: String,
-    pub state: String,
+pub struct Tender {
+    pub id: String,
+    pub amount_money: Option<Money>,
+    /// Set for card payments, which are what can be refunded online.
+    pub payment_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct LineItem {
-    pub uid: String,
-    pub name: String,
-    pub quantity: String,
-    pub catalog_object_id: String,
-    pub catalog_version: i64,
-    pub variation_name: String,
-    pub item_type: String,
-    pub base_price_money: Money,
-    pub variation_total_price_money: Money,
-    pub gross_sales_money: Money,
-    pub total_tax_money: Money,
-    pub total_discount_money: Money,
-    pub total_money: Money,
-    pub total_service_charge_money: Money,
+pub struct RefundPaymentRequest {
+    pub idempotency_key: String,
+    pub payment_id: String,
+    pub amount_money: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundPaymentResponse {
+    pub refund: Refund,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Refund {
+    pub id: String,
+    pub status: String,
 }
 
+/// An amount in the currency's smallest unit, e.g. pence.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Money {
     pub amount: i64,
     pub currency: Currency,
 }
 
+impl From<crate::money::Money> for Money {
+    fn from(m: crate::money::Money) -> Self {
+        Money {
+            amount: m.to_minor_units(),
+            currency: m.currency,
+        }
+    }
+}
+
+impl From<&Money> for crate::money::Money {
+    fn from(m: &Money) -> Self { crate::money::Money::from_minor_units(m.amount, m.currency) }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchCustomersRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<CustomerQuery>,
+    pub limit: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-pub enum Currency {
-    #[serde(rename = "GBP")]
-    Gbp,
+pub struct CustomerQuery {
+    pub filter: CustomerFilter,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CustomerFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_address: Option<CustomerTextFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<CustomerTextFilter>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct NetAmounts {
-    pub total_money: Money,
-    pub tax_money: Money,
-    pub discount_money: Money,
-    pub tip_money: Money,
-    pub service_charge_money: Money,
+pub struct CustomerTextFilter {
+    pub exact: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Source {
-    pub name: String,
+pub struct SearchCustomersResponse {
+    #[serde(default)]
+    pub customers: Vec<Customer>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkRetrieveCustomersRequest {
+    pub customer_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BulkRetrieveCustomersResponse {
+    /// Keyed by customer ID; IDs Square doesn't know come back with errors instead.
+    #[serde(default)]
+    pub responses: HashMap<String, RetrieveCustomerResponse>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RetrieveCustomerResponse {
+    pub customer: Option<Customer>,
+    #[serde(default)]
+    pub errors: Vec<ApiError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCustomerRequest {
+    pub idempotency_key: String,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub email_address: Option<String>,
+    pub phone_number: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCustomerResponse {
+    pub customer: Customer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Customer {
+    pub id: String,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub email_address: Option<String>,
+    pub phone_number: Option<String>,
+}
+
+/// The body Square sends back with a failed request.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    #[serde(default)]
+    pub errors: Vec<ApiError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiError {
+    pub category: String,
+    pub code: String,
+    pub detail: Option<String>,
+    pub field: Option<String>,
+}
+
+/// The parts of a webhook notification we act on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: WebhookData,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookData {
+    pub object: WebhookObject,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WebhookObject {
+    pub payment: Option<WebhookPayment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookPayment {
+    pub order_id: String,
+    pub status: String,
+}
@@ -0,0 +1,196 @@
+//! The one way the server talks to Square: shared configuration and auth,
+//! timeouts, retries for rate limiting and server errors, and Square's error
+//! responses decoded into something worth showing.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::*;
+
+use crate::config::{app_config, SquareConfig};
+use crate::error::ServiceError;
+use crate::square_api::*;
+
+/// How long to wait for Square before giving up on a request.
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// How many times to try a request that fails in a way that might not happen again.
+const ATTEMPTS: u32 = 3;
+/// The wait before the first retry, doubled for each one after.
+const BACKOFF: Duration = Duration::from_millis(250);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SquareError {
+    #[error("couldn't reach Square: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("Square rejected the request ({status}): {}", describe(.errors))]
+    Api { status: StatusCode, errors: Vec<ApiError> },
+}
+
+impl SquareError {
+    /// Square's error codes, e.g. `INVALID_EMAIL_ADDRESS`.
+    pub fn codes(&self) -> Vec<&str> {
+        match self {
+            SquareError::Api { errors, .. } => errors.iter().map(|e| e.code.as_str()).collect(),
+            SquareError::Transport(_) => vec![],
+        }
+    }
+}
+
+fn describe(errors: &[ApiError]) -> String {
+    errors
+        .iter()
+        .map(|e| match (&e.detail, &e.field) {
+            (Some(detail), Some(field)) => format!("{} ({})", detail, field),
+            (Some(detail), None) => detail.clone(),
+            (None, _) => e.code.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub struct SquareClient {
+    config: SquareConfig,
+    http: reqwest::Client,
+}
+
+static CLIENT: OnceLock<SquareClient> = OnceLock::new();
+
+/// Sets up the shared client at startup. Only the first call has any effect.
+pub fn init(config: SquareConfig) { CLIENT.get_or_init(|| SquareClient::new(config)); }
+
+/// The shared client. The backend sets it up at startup; failing that, it's set up
+/// from the config provided for the request, if there is one.
+pub fn square() -> Result<&'static SquareClient, ServiceError> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let config = app_config()?;
+    Ok(CLIENT.get_or_init(|| SquareClient::new(config.square)))
+}
+
+impl SquareClient {
+    pub fn new(config: SquareConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("HTTP client to build");
+        SquareClient { config, http }
+    }
+
+    pub fn config(&self) -> &SquareConfig { &self.config }
+
+    pub fn location_id(&self) -> &str { &self.config.location_id }
+
+    pub async fn create_payment_link(
+        &self,
+        req: &CreatePaymentLinkRequest,
+    ) -> Result<CreatePaymentLinkResponse, SquareError> {
+        self.send(Method::POST, "online-checkout/payment-links", Some(req)).await
+    }
+
+    pub async fn delete_payment_link(&self, link_id: &str) -> Result<DeletePaymentLinkResponse, SquareError> {
+        let path = format!("online-checkout/payment-links/{}", link_id);
+        self.send::<(), _>(Method::DELETE, &path, None).await
+    }
+
+    pub async fn create_order(&self, req: &CreateOrderRequest) -> Result<Order, SquareError> {
+        self.send::<_, OrderResponse>(Method::POST, "orders", Some(req))
+            .await
+            .map(|r| r.order)
+    }
+
+    pub async fn retrieve_order(&self, order_id: &str) -> Result<Order, SquareError> {
+        self.send::<(), OrderResponse>(Method::GET, &format!("orders/{}", order_id), None)
+            .await
+            .map(|r| r.order)
+    }
+
+    pub async fn update_order(&self, order_id: &str, req: &UpdateOrderRequest) -> Result<Order, SquareError> {
+        self.send::<_, OrderResponse>(Method::PUT, &format!("orders/{}", order_id), Some(req))
+            .await
+            .map(|r| r.order)
+    }
+
+    pub async fn search_orders(&self, req: &SearchOrdersRequest) -> Result<SearchOrdersResponse, SquareError> {
+        self.send(Method::POST, "orders/search", Some(req)).await
+    }
+
+    pub async fn search_customers(
+        &self,
+        req: &SearchCustomersRequest,
+    ) -> Result<SearchCustomersResponse, SquareError> {
+        self.send(Method::POST, "customers/search", Some(req)).await
+    }
+
+    pub async fn bulk_retrieve_customers(
+        &self,
+        req: &BulkRetrieveCustomersRequest,
+    ) -> Result<BulkRetrieveCustomersResponse, SquareError> {
+        self.send(Method::POST, "customers/bulk-retrieve", Some(req)).await
+    }
+
+    pub async fn create_customer(&self, req: &CreateCustomerRequest) -> Result<Customer, SquareError> {
+        self.send::<_, CreateCustomerResponse>(Method::POST, "customers", Some(req))
+            .await
+            .map(|r| r.customer)
+    }
+
+    pub async fn refund_payment(&self, req: &RefundPaymentRequest) -> Result<Refund, SquareError> {
+        self.send::<_, RefundPaymentResponse>(Method::POST, "refunds", Some(req))
+            .await
+            .map(|r| r.refund)
+    }
+
+    /// Sends a request, retrying when Square is rate limiting us, has a problem of
+    /// its own, or can't be reached. Everything we send is either a read or carries
+    /// an idempotency key, so repeating it is safe.
+    async fn send<B, R>(&self, method: Method, path: &str, body: Option<&B>) -> Result<R, SquareError>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        let url = format!("{}/v2/{}", self.config.base_url, path);
+        let mut attempt = 1;
+        loop {
+            let mut req = self
+                .http
+                .request(method.clone(), &url)
+                .bearer_auth(&self.config.api_key)
+                .header(reqwest::header::CONTENT_TYPE, "application/json");
+            if let Some(body) = body {
+                req = req.json(body);
+            }
+
+            let result = match req.send().await {
+                Ok(res) if res.status().is_success() => return Ok(res.json::<R>().await?),
+                Ok(res) => {
+                    let status = res.status();
+                    let errors = res.json::<ErrorResponse>().await.unwrap_or_default().errors;
+                    Err(SquareError::Api { status, errors })
+                }
+                Err(e) => Err(SquareError::Transport(e)),
+            };
+
+            let retryable = match &result {
+                Err(SquareError::Api { status, .. }) => {
+                    *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+                }
+                Err(SquareError::Transport(e)) => e.is_timeout() || e.is_connect(),
+                Ok(_) => false,
+            };
+            if !retryable || attempt >= ATTEMPTS {
+                if let Err(e) = &result {
+                    warn!(method = %method, path = path, error = %e, "square request failed");
+                }
+                return result;
+            }
+
+            let wait = BACKOFF * 2u32.pow(attempt - 1);
+            debug!(method = %method, path = path, attempt = attempt, "retrying square request");
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::{env, fs};
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::*;
+
+/// Directory the server keeps its persistent state in.
+pub fn data_dir() -> PathBuf { env::var("HAPPENINGS_DATA_DIR").unwrap_or_else(|_| "data".to_string()).into() }
+
+/// A small keyed collection that lives in memory and is written through to a
+/// JSON file, for state that Square doesn't hold for us.
+pub struct JsonStore<T> {
+    path: PathBuf,
+    items: RwLock<HashMap<String, T>>,
+}
+
+impl<T> JsonStore<T>
+where
+    T: Clone + Serialize + DeserializeOwned,
+{
+    /// Opens `<data dir>/<name>.json`, starting empty if it doesn't exist or can't be read.
+    pub fn open(name: &str) -> Self { Self::open_at(data_dir().join(format!("{}.json", name))) }
+
+    pub fn open_at<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let items = match fs::read_to_string(&path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                warn!(path = ?path, error = %e, "ignoring unreadable store");
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        JsonStore {
+            path,
+            items: RwLock::new(items),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<T> { self.items.read().unwrap().get(key).cloned() }
+
+    pub fn values(&self) -> Vec<T> { self.items.read().unwrap().values().cloned().collect() }
+
+    pub fn entries(&self) -> Vec<(String, T)> {
+        self.items
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    pub fn insert<K: Into<String>>(&self, key: K, value: T) -> Result<()> {
+        self.insert_many([(key.into(), value)])
+    }
+
+    pub fn insert_many<I: IntoIterator<Item = (String, T)>>(&self, entries: I) -> Result<()> {
+        let mut items = self.items.write().unwrap();
+        items.extend(entries);
+        self.save(&items)
+    }
+
+    /// Adds `value` under `key` unless something's already there, saying whether it was added.
+    pub fn insert_new<K: Into<String>>(&self, key: K, value: T) -> Result<bool> {
+        let mut items = self.items.write().unwrap();
+        match items.entry(key.into()) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                self.save(&items)?;
+                Ok(true)
+            }
+        }
+    }
+
+    pub fn remove(&self, key: &str) -> Result<Option<T>> {
+        let mut items = self.items.write().unwrap();
+        let removed = items.remove(key);
+        if removed.is_some() {
+            self.save(&items)?;
+        }
+        Ok(removed)
+    }
+
+    /// Applies `f` to the entry for `key` (if any) and persists the result.
+    pub fn update<F: FnOnce(&mut T)>(&self, key: &str, f: F) -> Result<Option<T>> {
+        let mut items = self.items.write().unwrap();
+        let updated = items.get_mut(key).map(|item| {
+            f(item);
+            item.clone()
+        });
+        if updated.is_some() {
+            self.save(&items)?;
+        }
+        Ok(updated)
+    }
+
+    fn save(&self, items: &HashMap<String, T>) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("creating {:?}", dir))?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(items)?).with_context(|| format!("writing {:?}", tmp))?;
+        fs::rename(&tmp, &self.path).with_context(|| format!("replacing {:?}", self.path))?;
+        Ok(())
+    }
+}
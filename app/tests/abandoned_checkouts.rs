@@ -0,0 +1,133 @@
+//! Lets payment links run out against the mock Square server, and checks what's
+//! taken down and given back when they do.
+//! Run with `cargo test -p app --features ssr`.
+#![cfg(feature = "ssr")]
+
+mod mock_square;
+
+use std::sync::OnceLock;
+
+use app::events::current_event;
+use app::model::*;
+use app::server_fns::{cancel_checkouts_expired_by, create_payment_link, list_discount_codes, save_discount_code};
+use chrono::{Duration, Utc};
+use mock_square::*;
+use rust_decimal_macros::dec;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Each test books as someone different, since customers are matched on email and phone number.
+fn booking(name: &str, email: &str, phone_no: &str) -> NewBooking {
+    let event = current_event();
+    let contact = BookingContact {
+        phone_no: phone_no.to_string(),
+        ..BookingContact::new(name, email, event.id.clone())
+    };
+    let tt = event.ticket_types.standard().unwrap();
+    NewBooking {
+        draft_id: uuid::Uuid::new_v4().to_string(),
+        event_id: event.id,
+        tickets: vec![Ticket::new(contact.id.clone(), tt.clone())],
+        contact,
+        ..Default::default()
+    }
+}
+
+/// Expiring checkouts takes down every one that's run out, not just the test's own,
+/// so the tests take turns.
+async fn one_at_a_time() -> MutexGuard<'static, ()> {
+    static TURN: OnceLock<Mutex<()>> = OnceLock::new();
+    TURN.get_or_init(Default::default).lock().await
+}
+
+/// Settles every checkout as though its time was up.
+async fn expire_checkouts() { cancel_checkouts_expired_by(Utc::now() + Duration::hours(1)).await }
+
+fn orders_for(mock: &MockSquare, email: &str) -> Vec<serde_json::Value> {
+    let state = mock.state();
+    let customer_id = state.customer_by_email(email).expect("customer to have been created")["id"].clone();
+    state.orders_for_customer(customer_id.as_str().unwrap())
+}
+
+#[tokio::test]
+async fn abandoned_checkouts_are_taken_down() {
+    let _turn = one_at_a_time().await;
+    let mock = mock_square();
+    create_payment_link(booking("Tiny Tim", "tim@example.com", "07700 900201")).await.unwrap();
+    let order_id = orders_for(mock, "tim@example.com")[0]["id"].clone();
+
+    expire_checkouts().await;
+
+    let state = mock.state();
+    assert!(state.payment_links.iter().all(|l| l["order_id"] != order_id));
+    assert_eq!(state.order(order_id.as_str().unwrap()).unwrap()["state"], "CANCELED");
+}
+
+#[tokio::test]
+async fn paid_checkouts_are_left_alone() {
+    let _turn = one_at_a_time().await;
+    let mock = mock_square();
+    create_payment_link(booking("Bob Cratchit", "bob@example.com", "07700 900202")).await.unwrap();
+    let order_id = orders_for(mock, "bob@example.com")[0]["id"].as_str().unwrap().to_string();
+    mock.pay(&order_id);
+
+    expire_checkouts().await;
+
+    let state = mock.state();
+    assert!(state.payment_links.iter().any(|l| l["order_id"] == order_id));
+    assert_eq!(state.order(&order_id).unwrap()["state"], "OPEN");
+}
+
+#[tokio::test]
+async fn an_expired_link_is_replaced_when_the_booking_is_resubmitted() {
+    let _turn = one_at_a_time().await;
+    let mock = mock_square();
+    let booking = booking("Belle Fezziwig", "belle@example.com", "07700 900203");
+
+    let first = create_payment_link(booking.clone()).await.unwrap();
+    expire_checkouts().await;
+    let second = create_payment_link(booking.clone()).await.unwrap();
+    let again = create_payment_link(booking).await.unwrap();
+
+    assert_ne!(first, second);
+    assert_eq!(second, again);
+    let orders = orders_for(mock, "belle@example.com");
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[0]["state"], "CANCELED");
+    assert_eq!(orders[1]["state"], "OPEN");
+}
+
+#[tokio::test]
+async fn a_discount_is_held_until_its_checkout_expires() {
+    let _turn = one_at_a_time().await;
+    mock_square();
+    sign_in_as_admin();
+    let code = DiscountCode {
+        code: "FEZZIWIG".to_string(),
+        event_id: current_event().id,
+        kind: DiscountKind::Percentage(dec!(50)),
+        max_uses: Some(1),
+        uses: 0,
+        held: 0,
+        expires_at: None,
+    };
+    save_discount_code(code.clone()).await.unwrap();
+    let discounted = |name: &str, email: &str, phone_no: &str| NewBooking {
+        discount: Some(code.clone()),
+        ..booking(name, email, phone_no)
+    };
+    let held = || async {
+        let codes = list_discount_codes().await.unwrap();
+        let saved = codes.iter().find(|d| d.code == "FEZZIWIG").unwrap();
+        (saved.uses, saved.held)
+    };
+
+    create_payment_link(discounted("Fan Scrooge", "fan@example.com", "07700 900204")).await.unwrap();
+    assert_eq!(held().await, (0, 1));
+    let late = discounted("Dick Wilkins", "dick@example.com", "07700 900205");
+    assert!(create_payment_link(late.clone()).await.is_err());
+
+    expire_checkouts().await;
+    assert_eq!(held().await, (0, 0));
+    create_payment_link(late).await.unwrap();
+    assert_eq!(held().await, (0, 1));
+}
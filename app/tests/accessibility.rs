@@ -0,0 +1,146 @@
+//! Renders the form controls as the server does and checks what screen readers
+//! get from the markup: every input named by a label, messages tied to what
+//! they're about, and dialogs marked up as dialogs.
+//! Run with `cargo test -p app --features ssr`.
+#![cfg(feature = "ssr")]
+
+use app::components::controls::*;
+use app::components::*;
+use leptos::*;
+use leptos_icons::FaIcon::*;
+
+fn render<N: IntoView + 'static>(view: impl FnOnce() -> N + 'static) -> String {
+    leptos::ssr::render_to_string(view).to_string()
+}
+
+/// The value of attribute `name` on the first element whose opening tag contains `marker`.
+fn attr<'a>(html: &'a str, marker: &str, name: &str) -> Option<&'a str> {
+    let at = html.find(marker)?;
+    let start = html[..=at].rfind('<')?;
+    let tag = &html[start..start + html[start..].find('>')?];
+    let key = format!(" {}=\"", name);
+    let from = tag.find(&key)? + key.len();
+    Some(&tag[from..from + tag[from..].find('"')?])
+}
+
+#[test]
+fn a_missing_name_is_labelled_and_described_by_its_message() {
+    let html = render(|| view! { <Name get="" set=|_| {}/> });
+
+    let id = attr(&html, "<input", "id").expect("the input to have an id");
+    assert_eq!(attr(&html, "<label", "for"), Some(id));
+    assert_eq!(attr(&html, "<input", "aria-invalid"), Some("true"));
+    assert_eq!(attr(&html, "<input", "aria-required"), Some("true"));
+    assert_eq!(
+        attr(&html, "<input", "aria-describedby"),
+        attr(&html, "help is-danger", "id")
+    );
+}
+
+#[test]
+fn a_valid_name_is_not_described_by_a_message() {
+    let html = render(|| view! { <Name get="Ada Lovelace" set=|_| {}/> });
+
+    assert_eq!(attr(&html, "<input", "aria-invalid"), Some("false"));
+    assert_eq!(attr(&html, "<input", "aria-describedby"), None);
+    assert!(!html.contains("help is-danger"));
+}
+
+#[test]
+fn an_invalid_email_is_described_by_why() {
+    let html = render(|| {
+        let (email, _) = create_signal("not an address".to_string());
+        view! { <Email get=email set=|_| {}/> }
+    });
+
+    let id = attr(&html, "<input", "id").expect("the input to have an id");
+    assert_eq!(attr(&html, "<label", "for"), Some(id));
+    assert_eq!(attr(&html, "<input", "type"), Some("email"));
+    assert_eq!(
+        attr(&html, "<input", "aria-describedby"),
+        attr(&html, "help is-danger", "id")
+    );
+}
+
+#[test]
+fn phone_number_and_country_each_have_a_label() {
+    let html = render(|| {
+        let (phone, _) = create_signal(String::new());
+        let (region, _) = create_signal("GB".to_string());
+        view! { <PhoneNumber get=phone set=|_| {} region=region set_region=|_| {}/> }
+    });
+
+    let country = attr(&html, "<select", "id").expect("the country to have an id");
+    let number = attr(&html, "<input", "id").expect("the number to have an id");
+    assert!(html.contains(&format!("for=\"{}\"", country)));
+    assert!(html.contains(&format!("for=\"{}\"", number)));
+    assert_eq!(
+        attr(&html, "<input", "aria-describedby"),
+        attr(&html, "help is-danger", "id")
+    );
+}
+
+#[test]
+fn checkboxes_and_text_inputs_are_labelled() {
+    let html = render(|| {
+        view! {
+          <Checkbox label="Vegetarian" get=Signal::derive(|| false) set=|_| {}/>
+          <Text placeholder="Other dietary requirements" get="" set=|_| {}/>
+        }
+    });
+
+    let checkbox = attr(&html, "type=\"checkbox\"", "id").expect("the checkbox to have an id");
+    let text = attr(&html, "type=\"text\"", "id").expect("the text input to have an id");
+    assert!(html.contains(&format!("for=\"{}\"", checkbox)));
+    assert!(html.contains(&format!("for=\"{}\"", text)));
+}
+
+#[test]
+fn a_field_names_its_controls_and_announces_its_problem() {
+    let html = render(|| {
+        let error = Signal::derive(|| Some("Please enter a valid email address".to_string()));
+        view! {
+          <Field label=|| "Booking Contact" error=error>
+            <Text placeholder="Name" get="" set=|_| {}/>
+          </Field>
+        }
+    });
+
+    assert_eq!(attr(&html, "field-body", "role"), Some("group"));
+    assert_eq!(
+        attr(&html, "field-body", "aria-labelledby"),
+        attr(&html, "class=\"label\"", "id")
+    );
+    assert_eq!(
+        attr(&html, "field-body", "aria-describedby"),
+        attr(&html, "help is-danger", "id")
+    );
+    assert_eq!(attr(&html, "help is-danger", "role"), Some("alert"));
+}
+
+#[test]
+fn a_modal_is_a_dialog_named_by_its_title() {
+    let html = render(|| {
+        view! {
+          <Modal active=|| true close_requested=|| {} title="Booking Summary" footer=|| ()>
+            <p>"Everything looks right"</p>
+          </Modal>
+        }
+    });
+
+    assert_eq!(attr(&html, "modal-card\"", "role"), Some("dialog"));
+    assert_eq!(attr(&html, "modal-card\"", "aria-modal"), Some("true"));
+    assert_eq!(
+        attr(&html, "modal-card\"", "aria-labelledby"),
+        attr(&html, "modal-card-title", "id")
+    );
+    assert_eq!(attr(&html, "class=\"delete\"", "aria-label"), Some("Close"));
+}
+
+#[test]
+fn icon_only_buttons_say_what_they_do() {
+    let html = render(|| view! { <IconButton on_click=|| {} icon=FaTrashSolid label="Remove ticket 2"/> });
+
+    assert_eq!(attr(&html, "<button", "aria-label"), Some("Remove ticket 2"));
+    assert_eq!(attr(&html, "class=\"icon", "aria-hidden"), Some("true"));
+}
@@ -0,0 +1,41 @@
+//! The site is shown in the closest locale we have to what the browser asks for,
+//! with money written the way that locale writes it.
+
+use app::i18n::I18n;
+use app::money::{Currency, Money};
+use rust_decimal_macros::dec;
+
+#[test]
+fn the_closest_locale_to_what_the_browser_asks_for_is_chosen() {
+    assert_eq!(I18n::from_accept_language("fr-CH, fr;q=0.9, en;q=0.8").lang(), "fr-FR");
+    assert_eq!(I18n::from_accept_language("en-US,en;q=0.5").lang(), "en-GB");
+    assert_eq!(I18n::from_accept_language("de-DE").lang(), "en-GB");
+    assert_eq!(I18n::negotiate(&["de", "fr-BE"]).lang(), "fr-FR");
+}
+
+#[test]
+fn money_is_written_the_locale_s_way() {
+    let (en, fr) = (I18n::new("en-GB"), I18n::new("fr-FR"));
+    let amount = Money::new(dec!(1234.5), Currency::Eur);
+
+    assert_eq!(en.money(&amount), "€1,234.50");
+    assert_eq!(fr.money(&amount), "1\u{202f}234,50\u{a0}€");
+    assert_eq!(en.money(&Money::new(dec!(-2.5), Currency::Gbp)), "-£2.50");
+    assert_eq!(en.money(&Money::new(dec!(1500), Currency::Jpy)), "¥1,500");
+}
+
+#[test]
+fn messages_are_translated_and_fall_back_to_english() {
+    let fr = I18n::new("fr-FR");
+    assert_eq!(fr.t("add-ticket"), "Ajouter un autre billet");
+    assert_eq!(fr.t_with("ticket-label", &[("number", 2.into())]), "Billet 2");
+    assert_eq!(fr.t("no-such-message"), "no-such-message");
+}
+
+#[test]
+fn counts_choose_the_right_plural() {
+    let en = I18n::default();
+    let subject = |tickets: usize| en.t_with("email-subject", &[("tickets", tickets.into()), ("name", "Sam".into())]);
+    assert_eq!(subject(1), "Xmas Dinner: 1 ticket booked by Sam");
+    assert_eq!(subject(3), "Xmas Dinner: 3 tickets booked by Sam");
+}
@@ -0,0 +1,439 @@
+//! An in-process stand-in for the parts of the Square API the app uses, so the
+//! server functions can be driven end to end without a network connection.
+//!
+//! It keeps just enough state to be convincing: customers, a small catalog,
+//! orders (created directly or through payment links), payments and refunds.
+//! Discounts and taxes aren't applied to order totals.
+//!
+//! Each test binary uses a different part of it.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use app::config::{AdminConfig, AppConfig, EmailConfig, SquareConfig};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+pub const LOCATION_ID: &str = "MOCKLOCATION";
+pub const WEBHOOK_KEY: &str = "mock-webhook-signature-key";
+pub const WEBHOOK_URL: &str = "https://happenings.example/webhooks/square";
+pub const ADMIN_PASSWORD: &str = "mock-admin-password";
+
+/// The catalog item the app sells standard tickets as, and its price in pence.
+pub const TICKET_ITEM_ID: &str = "VF54IAUH3FRNQMNE7T43ZXUB";
+pub const TICKET_PRICE: i64 = 1500;
+
+#[derive(Default)]
+pub struct MockState {
+    pub customers: Vec<Value>,
+    pub orders: Vec<Value>,
+    pub payment_links: Vec<Value>,
+    pub refunds: Vec<Value>,
+    /// Responses already given, by idempotency key.
+    idempotent: HashMap<String, Value>,
+    next_id: u64,
+}
+
+impl MockState {
+    fn id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{:06}", prefix, self.next_id)
+    }
+
+    pub fn order(&self, id: &str) -> Option<&Value> { self.orders.iter().find(|o| o["id"] == id) }
+
+    fn order_mut(&mut self, id: &str) -> Option<&mut Value> { self.orders.iter_mut().find(|o| o["id"] == id) }
+
+    pub fn orders_for_customer(&self, customer_id: &str) -> Vec<Value> {
+        self.orders
+            .iter()
+            .filter(|o| o["customer_id"] == customer_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn customer_by_email(&self, email: &str) -> Option<&Value> {
+        self.customers.iter().find(|c| c["email_address"] == email)
+    }
+}
+
+type Shared = Arc<Mutex<MockState>>;
+
+pub struct MockSquare {
+    state: Shared,
+    addr: String,
+}
+
+impl MockSquare {
+    pub fn state(&self) -> MutexGuard<'_, MockState> { self.state.lock().unwrap() }
+
+    /// Pays an order in full by card, as if the booker had completed the checkout.
+    pub fn pay(&self, order_id: &str) {
+        let mut state = self.state();
+        let payment_id = state.id("PAYMENT");
+        let order = state.order_mut(order_id).expect("order to exist");
+        let amount = order["total_money"]["amount"].clone();
+        order["tenders"] = json!([{
+            "id": payment_id,
+            "type": "CARD",
+            "payment_id": payment_id,
+            "amount_money": { "amount": amount, "currency": "GBP" },
+        }]);
+        order["version"] = json!(order["version"].as_i64().unwrap_or_default() + 1);
+    }
+}
+
+/// Starts the stand-in on first use and points the app at it.
+///
+/// The server gets its own thread and runtime because each test has its own
+/// runtime, which is gone once that test finishes.
+pub fn mock_square() -> &'static MockSquare {
+    static MOCK: OnceLock<MockSquare> = OnceLock::new();
+    MOCK.get_or_init(|| {
+        let state = Shared::default();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("a free port");
+        let addr = listener.local_addr().unwrap();
+
+        let app = router(state.clone());
+        std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async move {
+                axum::Server::from_tcp(listener)
+                    .unwrap()
+                    .serve(app.into_make_service())
+                    .await
+                    .unwrap()
+            })
+        });
+
+        let data_dir = std::env::temp_dir().join(format!("happenings-tests-{}", std::process::id()));
+        std::env::set_var("HAPPENINGS_DATA_DIR", data_dir);
+        app::square_client::init(square_config(addr.to_string()));
+
+        MockSquare { state, addr: addr.to_string() }
+    })
+}
+
+fn square_config(addr: String) -> SquareConfig {
+    SquareConfig {
+        base_url: format!("http://{}", addr),
+        api_key: "mock-api-key".to_string(),
+        location_id: LOCATION_ID.to_string(),
+        item_id: TICKET_ITEM_ID.to_string(),
+        catalog_version: 1700477397626,
+        webhook_signature_key: Some(WEBHOOK_KEY.to_string()),
+        webhook_url: WEBHOOK_URL.to_string(),
+    }
+}
+
+/// Makes the rest of the test's server function calls as a browser signed in to the
+/// admin pages would: with the admin password configured and its session cookie sent.
+pub fn sign_in_as_admin() {
+    let mock = mock_square();
+    let _ = leptos::create_runtime();
+    leptos::provide_context(AppConfig {
+        square: square_config(mock.addr.clone()),
+        email: EmailConfig {
+            host: "localhost".to_string(),
+            port: 25,
+            user: String::new(),
+            password: String::new(),
+        },
+        admin: AdminConfig {
+            password: Some(ADMIN_PASSWORD.to_string()),
+        },
+    });
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(ADMIN_PASSWORD.as_bytes()).unwrap();
+    mac.update(b"happenings admin session");
+    let session = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    let (parts, _) = axum::http::Request::builder()
+        .header(axum::http::header::COOKIE, format!("happenings_admin={}", session))
+        .body(())
+        .unwrap()
+        .into_parts();
+    leptos::provide_context(parts);
+}
+
+/// Signs a webhook body the way Square does.
+pub fn sign_webhook(body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_KEY.as_bytes()).unwrap();
+    mac.update(WEBHOOK_URL.as_bytes());
+    mac.update(body.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+fn router(state: Shared) -> Router {
+    Router::new()
+        .route("/v2/customers", post(create_customer))
+        .route("/v2/customers/search", post(search_customers))
+        .route("/v2/customers/bulk-retrieve", post(bulk_retrieve_customers))
+        .route("/v2/catalog/object/:id", get(retrieve_catalog_object))
+        .route("/v2/orders", post(create_order))
+        .route("/v2/orders/search", post(search_orders))
+        .route("/v2/orders/:id", get(retrieve_order).put(update_order))
+        .route("/v2/online-checkout/payment-links", post(create_payment_link))
+        .route("/v2/online-checkout/payment-links/:id", delete(delete_payment_link))
+        .route("/v2/refunds", post(refund_payment))
+        .with_state(state)
+}
+
+/// Replays the earlier response for a repeated idempotency key, as Square does.
+fn idempotent<F: FnOnce(&mut MockState) -> Value>(state: &Shared, req: &Value, f: F) -> Json<Value> {
+    let mut state = state.lock().unwrap();
+    let key = req["idempotency_key"].as_str().unwrap_or_default().to_string();
+    if let Some(earlier) = state.idempotent.get(&key) {
+        return Json(earlier.clone());
+    }
+    let res = f(&mut state);
+    state.idempotent.insert(key, res.clone());
+    Json(res)
+}
+
+async fn create_customer(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    idempotent(&state, &req, |state| {
+        let customer = json!({
+            "id": state.id("CUSTOMER"),
+            "given_name": req["given_name"],
+            "family_name": req["family_name"],
+            "email_address": req["email_address"],
+            "phone_number": req["phone_number"],
+        });
+        state.customers.push(customer.clone());
+        json!({ "customer": customer })
+    })
+}
+
+async fn search_customers(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    let state = state.lock().unwrap();
+    let filter = &req["query"]["filter"];
+    let matches = |c: &&Value| {
+        let email = filter["email_address"]["exact"].as_str();
+        let phone = filter["phone_number"]["exact"].as_str();
+        email.map_or(true, |e| c["email_address"] == e) && phone.map_or(true, |p| c["phone_number"] == p)
+    };
+    let customers = state.customers.iter().filter(matches).cloned().collect::<Vec<_>>();
+    Json(json!({ "customers": customers }))
+}
+
+async fn bulk_retrieve_customers(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    let state = state.lock().unwrap();
+    let ids = req["customer_ids"].as_array().cloned().unwrap_or_default();
+    let responses = ids
+        .iter()
+        .filter_map(|id| id.as_str())
+        .map(|id| {
+            let found = state.customers.iter().find(|c| c["id"] == id);
+            let res = match found {
+                Some(customer) => json!({ "customer": customer }),
+                None => json!({
+                    "errors": [{ "category": "INVALID_REQUEST_ERROR", "code": "NOT_FOUND" }],
+                }),
+            };
+            (id.to_string(), res)
+        })
+        .collect::<serde_json::Map<_, _>>();
+    Json(json!({ "responses": responses }))
+}
+
+async fn retrieve_catalog_object(Path(id): Path<String>) -> Json<Value> {
+    Json(json!({
+        "object": {
+            "type": "ITEM_VARIATION",
+            "id": id,
+            "item_variation_data": {
+                "name": catalog_name(&id),
+                "price_money": money(catalog_price(&id)),
+            },
+        },
+    }))
+}
+
+async fn create_order(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    idempotent(&state, &req, |state| {
+        let order = new_order(state, &req["order"]);
+        json!({ "order": order })
+    })
+}
+
+async fn create_payment_link(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    idempotent(&state, &req, |state| {
+        let order = new_order(state, &req["order"]);
+        let id = state.id("LINK");
+        let url = format!("https://square.link/u/{}", id);
+        let link = json!({
+            "id": id,
+            "version": 1,
+            "description": req["description"],
+            "order_id": order["id"],
+            "checkout_options": req["checkout_options"],
+            "url": url,
+            "long_url": format!("https://checkout.square.site/merchant/{}/order/{}", LOCATION_ID, id),
+            "created_at": "2023-11-20T12:00:00Z",
+        });
+        state.payment_links.push(link.clone());
+        json!({ "payment_link": link, "related_resources": { "orders": [order] } })
+    })
+}
+
+/// Takes the link down and cancels its order, as Square does.
+async fn delete_payment_link(State(state): State<Shared>, Path(id): Path<String>) -> Response {
+    let mut state = state.lock().unwrap();
+    let Some(at) = state.payment_links.iter().position(|l| l["id"] == id) else {
+        return not_found();
+    };
+    let link = state.payment_links.remove(at);
+    let order_id = link["order_id"].as_str().unwrap_or_default().to_string();
+    if let Some(order) = state.order_mut(&order_id) {
+        order["state"] = json!("CANCELED");
+        order["version"] = json!(order["version"].as_i64().unwrap_or_default() + 1);
+    }
+    Json(json!({ "id": id, "cancelled_order_id": order_id })).into_response()
+}
+
+async fn search_orders(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    let state = state.lock().unwrap();
+    let states = req["query"]["filter"]["state_filter"]["states"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let orders = state
+        .orders
+        .iter()
+        .filter(|o| states.is_empty() || states.contains(&o["state"]))
+        .cloned()
+        .collect::<Vec<_>>();
+    Json(json!({ "orders": orders }))
+}
+
+async fn retrieve_order(State(state): State<Shared>, Path(id): Path<String>) -> Response {
+    let state = state.lock().unwrap();
+    match state.order(&id) {
+        Some(order) => Json(json!({ "order": order })).into_response(),
+        None => not_found(),
+    }
+}
+
+async fn update_order(State(state): State<Shared>, Path(id): Path<String>, Json(req): Json<Value>) -> Json<Value> {
+    let mut state = state.lock().unwrap();
+    let order = state.order_mut(&id).expect("order to exist");
+    if let Some(new_state) = req["order"]["state"].as_str() {
+        order["state"] = json!(new_state);
+    }
+    order["version"] = json!(order["version"].as_i64().unwrap_or_default() + 1);
+    Json(json!({ "order": order }))
+}
+
+async fn refund_payment(State(state): State<Shared>, Json(req): Json<Value>) -> Json<Value> {
+    idempotent(&state, &req, |state| {
+        let refund = json!({
+            "id": state.id("REFUND"),
+            "status": "PENDING",
+            "payment_id": req["payment_id"],
+            "amount_money": req["amount_money"],
+        });
+        state.refunds.push(refund.clone());
+        json!({ "refund": refund })
+    })
+}
+
+fn new_order(state: &mut MockState, req: &Value) -> Value {
+    let line_items = req["line_items"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .iter()
+        .map(|li| {
+            let quantity = li["quantity"].as_str().unwrap_or("1").parse::<i64>().unwrap_or(1);
+            let (name, price) = match li["catalog_object_id"].as_str() {
+                Some(id) => (catalog_name(id), catalog_price(id)),
+                None => (
+                    li["name"].as_str().unwrap_or_default().to_string(),
+                    li["base_price_money"]["amount"].as_i64().unwrap_or_default(),
+                ),
+            };
+            let mut item = json!({
+                "uid": state.id("ITEM"),
+                "name": name,
+                "quantity": quantity.to_string(),
+                "variation_name": name,
+                "item_type": "ITEM",
+                "metadata": li["metadata"],
+                "base_price_money": money(price),
+                "variation_total_price_money": money(price * quantity),
+                "gross_sales_money": money(price * quantity),
+                "total_tax_money": money(0),
+                "total_discount_money": money(0),
+                "total_money": money(price * quantity),
+                "total_service_charge_money": money(0),
+            });
+            if let Some(id) = li["catalog_object_id"].as_str() {
+                item["catalog_object_id"] = json!(id);
+                item["catalog_version"] = li["catalog_version"].clone();
+            }
+            item
+        })
+        .collect::<Vec<_>>();
+
+    let total = line_items
+        .iter()
+        .map(|li| li["total_money"]["amount"].as_i64().unwrap_or_default())
+        .sum::<i64>();
+
+    let order = json!({
+        "id": state.id("ORDER"),
+        "location_id": req["location_id"],
+        "customer_id": req["customer_id"],
+        "source": { "name": "StukeleyHappenings" },
+        "line_items": line_items,
+        "metadata": req["metadata"],
+        "net_amounts": {
+            "total_money": money(total),
+            "tax_money": money(0),
+            "discount_money": money(0),
+            "tip_money": money(0),
+            "service_charge_money": money(0),
+        },
+        "created_at": "2023-11-20T12:00:00Z",
+        "updated_at": "2023-11-20T12:00:00Z",
+        "state": "OPEN",
+        "version": 1,
+        "total_money": money(total),
+        "total_tax_money": money(0),
+        "total_discount_money": money(0),
+        "total_tip_money": money(0),
+        "total_service_charge_money": money(0),
+        "net_amount_due_money": money(total),
+    });
+    state.orders.push(order.clone());
+    order
+}
+
+fn catalog_name(id: &str) -> String {
+    match id {
+        TICKET_ITEM_ID => "Adult".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn catalog_price(id: &str) -> i64 {
+    match id {
+        TICKET_ITEM_ID => TICKET_PRICE,
+        _ => 0,
+    }
+}
+
+fn not_found() -> Response {
+    let errors = json!({ "errors": [{ "category": "INVALID_REQUEST_ERROR", "code": "NOT_FOUND" }] });
+    (StatusCode::NOT_FOUND, Json(errors)).into_response()
+}
+
+fn money(amount: i64) -> Value { json!({ "amount": amount, "currency": "GBP" }) }
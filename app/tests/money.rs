@@ -0,0 +1,42 @@
+//! Money crosses to Square in minor units, so each currency has to get those right.
+
+use app::money::{Currency, Money, Totals};
+use rust_decimal_macros::dec;
+
+#[test]
+fn amounts_go_to_and_from_minor_units_for_their_currency() {
+    assert_eq!(Money::new(dec!(15.00), Currency::Gbp).to_minor_units(), 1500);
+    assert_eq!(Money::new(dec!(12.344), Currency::Eur).to_minor_units(), 1234);
+    assert_eq!(Money::new(dec!(1500), Currency::Jpy).to_minor_units(), 1500);
+
+    assert_eq!(Money::from_minor_units(1500, Currency::Gbp), Money::new(dec!(15), Currency::Gbp));
+    assert_eq!(Money::from_minor_units(1500, Currency::Jpy), Money::new(dec!(1500), Currency::Jpy));
+}
+
+#[test]
+fn amounts_are_shown_with_their_currency() {
+    assert_eq!(Money::new(dec!(15), Currency::Gbp).to_string(), "£15.00");
+    assert_eq!(Money::new(dec!(-2.5), Currency::Eur).to_string(), "-€2.50");
+    assert_eq!(Money::new(dec!(1500), Currency::Jpy).to_string(), "¥1500");
+}
+
+#[test]
+fn totals_are_kept_per_currency() {
+    let totals = [
+        Money::new(dec!(5), Currency::Gbp),
+        Money::new(dec!(3), Currency::Eur),
+        Money::new(dec!(2), Currency::Gbp),
+    ]
+    .into_iter()
+    .collect::<Totals>();
+    assert_eq!(totals.to_string(), "£7.00 + €3.00");
+}
+
+#[test]
+fn amounts_stored_before_currencies_are_read_as_pounds() {
+    let money: Money = serde_json::from_str(r#""15.00""#).unwrap();
+    assert_eq!(money, Money::new(dec!(15), Currency::Gbp));
+
+    let money: Money = serde_json::from_str(r#"{"amount":"20","currency":"EUR"}"#).unwrap();
+    assert_eq!(money, Money::new(dec!(20), Currency::Eur));
+}
@@ -0,0 +1,271 @@
+//! Drives the booking server functions end to end against the mock Square server.
+//! Run with `cargo test -p app --features ssr`.
+#![cfg(feature = "ssr")]
+
+mod mock_square;
+
+use app::error::ErrorReport;
+use app::events::current_event;
+use app::model::*;
+use app::money::{Currency, Money};
+use app::payments::{square::square_webhook, PaymentProvider, PaymentState, SquareCheckout};
+use app::server_fns::{create_order, create_payment_link, fetch_bookings, list_bookings, list_discount_codes,
+                      save_discount_code};
+use axum::body::Bytes;
+use axum::http::{HeaderMap, StatusCode};
+use mock_square::*;
+use rust_decimal_macros::dec;
+
+/// Each test books as someone different, since customers are matched on email and phone number.
+fn booking(name: &str, email: &str, phone_no: &str, tickets: usize) -> NewBooking {
+    let event = current_event();
+    let contact = BookingContact {
+        phone_no: phone_no.to_string(),
+        ..BookingContact::new(name, email, event.id.clone())
+    };
+    let tt = event.ticket_types.standard().unwrap();
+    NewBooking {
+        draft_id: uuid::Uuid::new_v4().to_string(),
+        event_id: event.id,
+        tickets: (0..tickets).map(|_| Ticket::new(contact.id.clone(), tt.clone())).collect(),
+        contact,
+        ..Default::default()
+    }
+}
+
+fn pounds(pence: i64) -> Money { Money::from_minor_units(pence, Currency::Gbp) }
+
+fn customer_id(email: &str) -> String {
+    mock_square()
+        .state()
+        .customer_by_email(email)
+        .and_then(|c| c["id"].as_str())
+        .expect("customer to have been created")
+        .to_string()
+}
+
+#[tokio::test]
+async fn payment_link_creates_an_order_for_the_booking() {
+    let mock = mock_square();
+    let url = create_payment_link(booking("Mary Christmas", "mary@example.com", "07700 900101", 2)).await.unwrap();
+    assert!(url.starts_with("https://checkout.square.site/"));
+
+    let orders = mock.state().orders_for_customer(&customer_id("mary@example.com"));
+    assert_eq!(orders.len(), 1);
+    let order = &orders[0];
+    assert_eq!(order["location_id"], LOCATION_ID);
+    assert_eq!(order["metadata"]["event_id"], current_event().id);
+    assert_eq!(order["line_items"].as_array().unwrap().len(), 2);
+    assert_eq!(order["total_money"]["amount"], 2 * TICKET_PRICE);
+
+    let state = mock.state();
+    let link = state
+        .payment_links
+        .iter()
+        .find(|l| l["order_id"] == order["id"])
+        .expect("a payment link for the order");
+    assert_eq!(link["description"], current_event().checkout.description);
+}
+
+#[tokio::test]
+async fn resubmitting_a_booking_reuses_its_payment_link() {
+    let mock = mock_square();
+    let booking = booking("Holly Berry", "holly@example.com", "07700 900102", 1);
+
+    let first = create_payment_link(booking.clone()).await.unwrap();
+    let second = create_payment_link(booking).await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(mock.state().orders_for_customer(&customer_id("holly@example.com")).len(), 1);
+}
+
+#[tokio::test]
+async fn returning_bookers_keep_their_customer() {
+    let mock = mock_square();
+    sign_in_as_admin();
+    create_order(booking("Ivy Green", "ivy@example.com", "07700 900103", 1)).await.unwrap();
+    create_order(booking("Ivy Green", "ivy@example.com", "07700 900103", 3)).await.unwrap();
+
+    let customer_id = customer_id("ivy@example.com");
+    let state = mock.state();
+    let customers = state
+        .customers
+        .iter()
+        .filter(|c| c["email_address"] == "ivy@example.com")
+        .count();
+    assert_eq!(customers, 1);
+    assert_eq!(state.orders_for_customer(&customer_id).len(), 2);
+}
+
+#[tokio::test]
+async fn created_orders_are_listed_as_bookings() {
+    mock_square();
+    sign_in_as_admin();
+    let order_id = create_order(booking("Rudy Nose", "rudy@example.com", "07700 900104", 2)).await.unwrap();
+
+    let bookings = fetch_bookings().await.unwrap();
+    let booking = bookings.iter().find(|b| b.id == order_id).expect("the order to be listed");
+
+    assert_eq!(booking.event_id, current_event().id);
+    assert_eq!(booking.contact.email, "rudy@example.com");
+    assert_eq!(booking.tickets.len(), 2);
+    assert_eq!(booking.tickets[0].ticket_type.name, "Adult");
+    assert_eq!(booking.price(), pounds(2 * TICKET_PRICE));
+    assert_eq!(booking.payment, BookingPayment::NotPaid);
+}
+
+#[tokio::test]
+async fn bookings_are_only_listed_for_admins() {
+    mock_square();
+    assert!(list_bookings().await.is_err());
+}
+
+#[tokio::test]
+async fn only_admins_can_book_without_paying() {
+    let mock = mock_square();
+    assert!(create_order(booking("Robin Redbreast", "robin@example.com", "07700 900110", 1)).await.is_err());
+    assert!(mock.state().customer_by_email("robin@example.com").is_none());
+}
+
+#[tokio::test]
+async fn creating_the_same_order_twice_uses_its_discount_once() {
+    mock_square();
+    sign_in_as_admin();
+    let event = current_event();
+    let code = DiscountCode {
+        code: "TWICE".to_string(),
+        event_id: event.id.clone(),
+        kind: DiscountKind::Percentage(dec!(10)),
+        max_uses: None,
+        uses: 0,
+        held: 0,
+        expires_at: None,
+    };
+    save_discount_code(code.clone()).await.unwrap();
+    let booking = NewBooking {
+        discount: Some(code),
+        ..booking("Angel Gabriel", "angel@example.com", "07700 900111", 1)
+    };
+
+    let first = create_order(booking.clone()).await.unwrap();
+    let second = create_order(booking).await.unwrap();
+
+    assert_eq!(first, second);
+    let saved = list_discount_codes().await.unwrap();
+    let saved = saved.iter().find(|d| d.code == "TWICE").unwrap();
+    assert_eq!((saved.uses, saved.held), (1, 0));
+}
+
+#[tokio::test]
+async fn refused_bookings_say_what_to_fix() {
+    mock_square();
+    let refused = NewBooking {
+        donation: pounds(-100),
+        ..booking("Noel Grinch", "not an email", "07700 900106", 0)
+    };
+
+    let err = create_payment_link(refused).await.unwrap_err();
+    let report = ErrorReport::from(&err);
+    assert!(report.correlation_id.is_some());
+    let fields = report.error.field_errors().expect("field errors");
+    assert_eq!(fields["donation"], "A donation can't be negative");
+    assert_eq!(fields["contact.email"], "Please enter a valid email address");
+    assert_eq!(fields["tickets"], "Please add at least one ticket");
+    assert!(mock_square().state().customer_by_email("not an email").is_none());
+}
+
+#[tokio::test]
+async fn tickets_must_be_at_the_events_price() {
+    mock_square();
+    sign_in_as_admin();
+    let mut cheap = booking("Jack Frost", "jack@example.com", "07700 900107", 2);
+    cheap.tickets[1].ticket_type.price = pounds(1);
+
+    let err = create_order(cheap).await.unwrap_err();
+    let report = ErrorReport::from(&err);
+    let fields = report.error.field_errors().expect("field errors");
+    assert_eq!(fields.keys().collect::<Vec<_>>(), ["tickets.1"]);
+}
+
+#[tokio::test]
+async fn standard_tickets_cant_be_passed_off_as_a_bundle() {
+    let mock = mock_square();
+    sign_in_as_admin();
+    let mut forged = booking("Eve Bundle", "eve@example.com", "07700 900108", 3);
+    for ticket in forged.tickets.iter_mut() {
+        ticket.bundle = Some(BundleMember {
+            bundle_id: "forged".to_string(),
+            label: "Adult".to_string(),
+        });
+    }
+
+    let err = create_order(forged.clone()).await.unwrap_err();
+    let report = ErrorReport::from(&err);
+    let fields = report.error.field_errors().expect("field errors");
+    assert_eq!(fields.keys().collect::<Vec<_>>(), ["tickets.0", "tickets.1", "tickets.2"]);
+    assert!(create_payment_link(forged).await.is_err());
+    assert!(mock.state().customer_by_email("eve@example.com").is_none());
+}
+
+#[tokio::test]
+async fn phone_numbers_are_kept_in_international_form() {
+    let mock = mock_square();
+    sign_in_as_admin();
+    let mut booking = booking("Marie Noel", "marie@example.com", "06 12 34 56 78", 1);
+    booking.contact.phone_region = "FR".to_string();
+    create_order(booking).await.unwrap();
+
+    let state = mock.state();
+    let customer = state.customer_by_email("marie@example.com").unwrap();
+    assert_eq!(customer["phone_number"], "+33612345678");
+}
+
+#[tokio::test]
+async fn paid_orders_can_be_refunded() {
+    let mock = mock_square();
+    sign_in_as_admin();
+    let order_id = create_order(booking("Carol Singer", "carol@example.com", "07700 900105", 1)).await.unwrap();
+
+    assert_eq!(SquareCheckout.payment_state(&order_id).await.unwrap(), PaymentState::Unpaid);
+    mock.pay(&order_id);
+    assert_eq!(
+        SquareCheckout.payment_state(&order_id).await.unwrap(),
+        PaymentState::Paid(pounds(TICKET_PRICE))
+    );
+
+    SquareCheckout.refund(&order_id, "carol-1", pounds(500)).await.unwrap();
+    let state = mock.state();
+    let refund = state.refunds.last().unwrap();
+    assert_eq!(refund["amount_money"]["amount"], 500);
+}
+
+#[tokio::test]
+async fn refunds_of_the_same_amount_are_each_given_once() {
+    let mock = mock_square();
+    sign_in_as_admin();
+    let order_id = create_order(booking("Gabriel Bell", "gabriel@example.com", "07700 900109", 2)).await.unwrap();
+    mock.pay(&order_id);
+    let payment_id = mock.state().order(&order_id).unwrap()["tenders"][0]["payment_id"].clone();
+
+    SquareCheckout.refund(&order_id, "gabriel-1", pounds(500)).await.unwrap();
+    SquareCheckout.refund(&order_id, "gabriel-1", pounds(500)).await.unwrap();
+    SquareCheckout.refund(&order_id, "gabriel-2", pounds(500)).await.unwrap();
+
+    let state = mock.state();
+    let refunds = state.refunds.iter().filter(|r| r["payment_id"] == payment_id).count();
+    assert_eq!(refunds, 2);
+}
+
+#[tokio::test]
+async fn webhooks_must_be_signed() {
+    mock_square();
+    let body = r#"{"type":"payment.updated","data":{"object":{"payment":{"order_id":"ORDER1","status":"COMPLETED"}}}}"#;
+
+    let mut signed = HeaderMap::new();
+    signed.insert("x-square-hmacsha256-signature", sign_webhook(body).parse().unwrap());
+    assert_eq!(square_webhook(signed, Bytes::from(body)).await, StatusCode::OK);
+
+    let mut forged = HeaderMap::new();
+    forged.insert("x-square-hmacsha256-signature", sign_webhook("{}").parse().unwrap());
+    assert_eq!(square_webhook(forged, Bytes::from(body)).await, StatusCode::FORBIDDEN);
+}
@@ -1,17 +1,38 @@
+use app::config::{check_events, AppConfig};
 use app::*;
 use async_signals::Signals;
-use axum::{routing::post, Router};
+use axum::body::Body;
+use axum::extract::{Path, RawQuery};
+use axum::http::{HeaderMap, Request};
+use axum::response::IntoResponse;
+use axum::{routing::post, Extension, Router};
 use dotenv::dotenv;
 use fileserv::file_and_error_handler;
 use futures_util::StreamExt;
 use leptos::*;
-use leptos_axum::{generate_route_list, LeptosRoutes};
+use leptos_axum::{generate_route_list, handle_server_fns_with_context, LeptosRoutes};
 use nix::{libc, sys::signal::Signal};
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tracing::*;
 
 pub mod fileserv;
 
+/// Provides the app config to whatever is being rendered or called.
+fn with_config(config: AppConfig) -> impl Fn() + Clone + Send + 'static {
+    move || provide_context(config.clone())
+}
+
+async fn server_fn_handler(
+    Extension(config): Extension<AppConfig>,
+    path: Path<String>,
+    headers: HeaderMap,
+    raw_query: RawQuery,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    handle_server_fns_with_context(path, headers, raw_query, with_config(config), req).await
+}
+
 #[tokio::main]
 async fn main() {
     // pretty_env_logger::init();
@@ -32,6 +53,15 @@ async fn main() {
 
     info!("Off we go!");
 
+    let config = match AppConfig::load().and_then(|config| check_events().map(|_| config)) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    square_client::init(config.square.clone());
+
     let conf = get_configuration(None).await.unwrap();
     let leptos_options = conf.leptos_options;
     let addr = leptos_options.site_addr;
@@ -39,11 +69,22 @@ async fn main() {
 
     // build our application with a route
     let app = Router::new()
-        .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
-        .leptos_routes(&leptos_options, routes, App)
+        .route("/api/*fn_name", post(server_fn_handler))
+        .route("/webhooks/square", post(payments::square::square_webhook))
+        .leptos_routes_with_context(&leptos_options, routes, with_config(config.clone()), App)
+        .layer(Extension(config))
         .fallback(file_and_error_handler)
         .with_state(leptos_options);
 
+    // Tidy up checkouts that were started but never paid for.
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(5 * 60));
+        loop {
+            interval.tick().await;
+            server_fns::cancel_abandoned_checkouts().await;
+        }
+    });
+
     info!("listening on http://{}", &addr);
 
     // Channel for graceful shutdown
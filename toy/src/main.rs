@@ -2,6 +2,7 @@ use std::env;
 
 use anyhow::Result;
 use app::model::*;
+use app::money::{Currency, Money};
 use css_inline::CSSInliner;
 use dotenv::dotenv;
 use leptos::*;
@@ -140,15 +141,20 @@ async fn main() {
 
     let test_ticket = Ticket {
         booking_id: "abc".to_string(),
+        attendee_name: "".to_string(),
         vegetarian: true,
         gluten_free: false,
         dietary_requirements: "only cheese".to_string(),
+        bundle: None,
 
         ticket_type: TicketType {
             name: "Adult".to_string(),
-            price: Decimal::new(15, 2),
+            price: Money::new(Decimal::new(15, 2), Currency::Gbp),
             square_item_id: "foo".to_string(),
             square_catalog_version: 42,
+            on_sale_from: None,
+            on_sale_until: None,
+            bundle: vec![],
         },
     };
 
@@ -161,9 +167,13 @@ async fn main() {
             email: "the@rock.com".to_string(),
             event_id: "fancyid".to_string(),
             phone_no: "123456".to_string(),
+            phone_region: "GB".to_string(),
+            marketing_consent: false,
         },
         payment: BookingPayment::NotPaid,
         tickets: vec![test_ticket],
+        add_ons: vec![],
+        donation: Money::zero(Currency::Gbp),
     };
 
     let ret = email_booking(b).await;